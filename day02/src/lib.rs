@@ -1,28 +1,26 @@
+use std::error::Error;
+use std::fmt;
+use std::num::ParseIntError;
 use std::ops::RangeInclusive;
+use itertools::Itertools;
 
 //
 // For the given ranges, find all numbers that consist of a sequence of
 // digits repeated twice.  Return their sum.
 //
-pub fn part1(input: &str) -> u64 {
-    parse_input(input)
+pub fn part1(input: &str) -> u128 {
+    part1_radix(input, 10)
+}
+
+//
+// Like `part1`, but the IDs (and their digit patterns) are written in
+// base `radix`.
+//
+pub fn part1_radix(input: &str, radix: u32) -> u128 {
+    parse_input(input, radix)
         .into_iter()
         .flatten()
-        .filter(|num| {
-            match num {
-                10 ..= 99 =>                    // 2 digits; pattern is 1 digit
-                    num % 11 == 0,
-                1000 ..= 9999 =>                // 4 digits; pattern is 2 digits
-                    num % 101 == 0,
-                100000 ..= 999999 =>            // 6 digits; pattern is 3 digits
-                    num % 1001 == 0,
-                10000000 ..= 99999999 =>        // 8 digits; pattern is 4 digits
-                    num % 10001 == 0,
-                1000000000 ..= 9999999999 =>    // 10 digits; pattern is 5 digits
-                    num % 100001 == 0,
-                _ => false
-            }
-        })
+        .filter(|&num| is_doubled(num, radix))
         // .inspect(|num| println!("num={num}"))
         .sum()
 }
@@ -31,53 +29,426 @@ pub fn part1(input: &str) -> u64 {
 // For the given ranges, find all numbers that consist of a sequence of
 // digits repeated at least twice.  Return their sum.
 //
-// NOTE: The largest numbers in our full input have 10 digits.
+pub fn part2(input: &str) -> u128 {
+    part2_radix(input, 10)
+}
+
+//
+// Like `part2`, but the IDs (and their digit patterns) are written in
+// base `radix`.
 //
-pub fn part2(input: &str) -> u64 {
-    parse_input(input)
+pub fn part2_radix(input: &str, radix: u32) -> u128 {
+    parse_input(input, radix)
         .into_iter()
         .flatten()
-        .filter(|num| {
-            match num {
-                10 ..= 99 =>                    // 2 digits; pattern is 1 digit
-                    num % 11 == 0,
-                100 ..= 999 =>                  // 3 digits; pattern is 1 digit
-                    num % 111 == 0,
-                1000 ..= 9999 =>                // 4 digits; pattern is 2 digits
-                    num % 101 == 0,
-                10000 ..= 99999 =>              // 5 digits; pattern is 1 digit
-                    num % 11111 == 0,
-                100000 ..= 999999 =>            // 6 digits; pattern is 2 or 3 digits
-                    num % 10101 == 0 || num % 1001 == 0,
-                1000000 ..= 9999999 =>          // 7 digits; pattern is 1 digit
-                    num % 1111111 == 0,
-                10000000 ..= 99999999 =>        // 8 digits; pattern is 2 or 4 digits
-                    num % 1010101 == 0 || num % 10001 == 0,
-                100000000 ..= 999999999 =>      // 9 ditits; pattern is 3 digits
-                    num % 1001001 == 0,
-                1000000000 ..= 9999999999 =>    // 10 digits; pattern is 2 or 5 digits
-                    num % 101010101 == 0 || num % 100001 == 0,
-                _ => false
-            }
-        })
+        .filter(|&num| is_repeated(num, radix))
         // .inspect(|num| println!("num={num}"))
         .sum()
 }
 
-fn parse_input(input: &str) -> Vec<RangeInclusive<u64>> {
-    input
-        .trim_end()
-        .split(',')
-        .map(|s| {
-            let mut ends = s.split('-').map(|num_str| num_str.parse::<u64>().unwrap());
-            ends.next().unwrap() ..= ends.next().unwrap()
+//
+// Is `num` (in base `radix`) a sequence of digits repeated exactly twice?
+//
+pub fn is_doubled(num: u128, radix: u32) -> bool {
+    if radix == 10
+        && let Ok(num) = u64::try_from(num)
+        && let Some(result) = is_doubled_decimal(num)
+    {
+        return result;
+    }
+
+    has_repeats(num, radix, 2)
+}
+
+//
+// Is `num` (in base `radix`) a sequence of digits repeated at least twice?
+//
+pub fn is_repeated(num: u128, radix: u32) -> bool {
+    if radix == 10
+        && let Ok(num) = u64::try_from(num)
+        && let Some(result) = is_repeated_decimal(num)
+    {
+        return result;
+    }
+
+    let digits = num_digits(num, radix);
+    (2..=digits).any(|repeats| has_repeats(num, radix, repeats))
+}
+
+//
+// Fast path for `is_doubled` with decimal numbers of up to 10 digits.
+// Returns `None` if `num` is too large for the table.
+//
+fn is_doubled_decimal(num: u64) -> Option<bool> {
+    match num {
+        0 ..= 9 =>                      // 1 digit; can't be repeated
+            Some(false),
+        10 ..= 99 =>                    // 2 digits; pattern is 1 digit
+            Some(num.is_multiple_of(11)),
+        1000 ..= 9999 =>                // 4 digits; pattern is 2 digits
+            Some(num.is_multiple_of(101)),
+        100000 ..= 999999 =>            // 6 digits; pattern is 3 digits
+            Some(num.is_multiple_of(1001)),
+        10000000 ..= 99999999 =>        // 8 digits; pattern is 4 digits
+            Some(num.is_multiple_of(10001)),
+        1000000000 ..= 9999999999 =>    // 10 digits; pattern is 5 digits
+            Some(num.is_multiple_of(100001)),
+        10000000000 .. =>               // more than 10 digits
+            None,
+        _ =>                            // odd number of digits
+            Some(false)
+    }
+}
+
+//
+// Fast path for `is_repeated` with decimal numbers of up to 10 digits.
+// Returns `None` if `num` is too large for the table.
+//
+fn is_repeated_decimal(num: u64) -> Option<bool> {
+    match num {
+        0 ..= 9 =>                      // 1 digit; can't be repeated
+            Some(false),
+        10 ..= 99 =>                    // 2 digits; pattern is 1 digit
+            Some(num.is_multiple_of(11)),
+        100 ..= 999 =>                  // 3 digits; pattern is 1 digit
+            Some(num.is_multiple_of(111)),
+        1000 ..= 9999 =>                // 4 digits; pattern is 2 digits
+            Some(num.is_multiple_of(101)),
+        10000 ..= 99999 =>              // 5 digits; pattern is 1 digit
+            Some(num.is_multiple_of(11111)),
+        100000 ..= 999999 =>            // 6 digits; pattern is 2 or 3 digits
+            Some(num.is_multiple_of(10101) || num.is_multiple_of(1001)),
+        1000000 ..= 9999999 =>          // 7 digits; pattern is 1 digit
+            Some(num.is_multiple_of(1111111)),
+        10000000 ..= 99999999 =>        // 8 digits; pattern is 2 or 4 digits
+            Some(num.is_multiple_of(1010101) || num.is_multiple_of(10001)),
+        100000000 ..= 999999999 =>      // 9 digits; pattern is 3 digits
+            Some(num.is_multiple_of(1001001)),
+        1000000000 ..= 9999999999 =>    // 10 digits; pattern is 2 or 5 digits
+            Some(num.is_multiple_of(101010101) || num.is_multiple_of(100001)),
+        _ => None
+    }
+}
+
+//
+// Does `num` (in base `radix`) consist of some pattern of digits repeated
+// exactly `repeats` times?
+//
+// If the pattern is `p` digits long, then `num` is the pattern multiplied
+// by 1 + radix^p + radix^2p + ... (one term per repeat).  That multiplier
+// is the generalization of the constants in the decimal tables above.
+// The pattern can't start with a zero, because that would make `num`
+// shorter than `repeats * p` digits.
+//
+fn has_repeats(num: u128, radix: u32, repeats: u32) -> bool {
+    let digits = num_digits(num, radix);
+    if repeats < 2 || !digits.is_multiple_of(repeats) {
+        return false;
+    }
+
+    // If the multiplier overflows, it is larger than `num`, so can't divide it.
+    let pattern_len = digits / repeats;
+    u128::from(radix).checked_pow(pattern_len)
+        .and_then(|shift| {
+            (0..repeats).try_fold(0u128, |multiplier, _| {
+                multiplier.checked_mul(shift)?.checked_add(1)
+            })
+        })
+        .is_some_and(|multiplier| num.is_multiple_of(multiplier))
+}
+
+//
+// The number of digits needed to write `num` in base `radix`.
+//
+fn num_digits(num: u128, radix: u32) -> u32 {
+    num.checked_ilog(radix.into()).map_or(1, |log| log + 1)
+}
+
+//
+// Which numbers count as invalid IDs: a pattern repeated exactly twice
+// (part 1), or repeated any number of times, at least twice (part 2).
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeats {
+    Twice,
+    AtLeastTwice,
+}
+
+//
+// An invalid ID, along with the pattern of digits that repeats within it.
+// If there are several possible patterns (e.g. 222222 could be "2" x6,
+// "22" x3, or "222" x2), this is the shortest pattern allowed by the rule.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidId {
+    pub id: u128,
+    pub unit: String,
+    pub repeats: u32,
+}
+
+impl fmt::Display for InvalidId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = \"{}\" x{}", self.unit.repeat(self.repeats as usize), self.unit, self.repeats)
+    }
+}
+
+//
+// All of the invalid IDs within one of the input ranges.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeReport {
+    pub range: RangeInclusive<u128>,
+    pub radix: u32,
+    pub invalid: Vec<InvalidId>,
+}
+
+impl RangeReport {
+    pub fn count(&self) -> usize {
+        self.invalid.len()
+    }
+
+    pub fn sum(&self) -> u128 {
+        self.invalid.iter().map(|invalid| invalid.id).sum()
+    }
+}
+
+impl fmt::Display for RangeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}-{}: {} invalid, sum {}",
+            to_radix_string(*self.range.start(), self.radix),
+            to_radix_string(*self.range.end(), self.radix),
+            self.count(), self.sum())?;
+        for invalid in &self.invalid {
+            writeln!(f, "    {invalid}")?;
+        }
+        Ok(())
+    }
+}
+
+//
+// For each of the input ranges, list the invalid IDs according to `rule`.
+// The sum of the per-range sums is the answer to part 1 or part 2 (as
+// long as no ranges overlap).
+//
+pub fn report(input: &str, radix: u32, rule: Repeats) -> Vec<RangeReport> {
+    let options = ParseOptions { radix, keep_duplicates: true, ..ParseOptions::default() };
+    let ranges = parse_ranges(input, &options).expect("Invalid input");
+    report_ranges(ranges, radix, rule)
+}
+
+//
+// Like `report`, but with overlapping ranges merged first, so that each
+// invalid ID is listed once, in order.
+//
+pub fn report_merged(input: &str, radix: u32, rule: Repeats) -> Vec<RangeReport> {
+    report_ranges(parse_input(input, radix), radix, rule)
+}
+
+//
+// Like `report`, for ranges that have already been parsed (for example,
+// by `parse_ranges` with non-default options).  Panics if `radix` is not
+// between 2 and 36.
+//
+pub fn report_ranges(ranges: Vec<RangeInclusive<u128>>, radix: u32, rule: Repeats) -> Vec<RangeReport> {
+    assert!((2..=36).contains(&radix), "radix {radix} is not between 2 and 36");
+    ranges
+        .into_iter()
+        .map(|range| {
+            let invalid = range.clone()
+                .filter_map(|num| invalid_id(num, radix, rule))
+                .collect();
+            RangeReport { range, radix, invalid }
+        })
+        .collect()
+}
+
+//
+// If `num` is an invalid ID according to `rule`, return the repeating
+// pattern and how many times it repeats.
+//
+pub fn invalid_id(num: u128, radix: u32, rule: Repeats) -> Option<InvalidId> {
+    // Use the fast tests to weed out the (vast majority of) valid IDs.
+    let repeats = match rule {
+        Repeats::Twice => {
+            if !is_doubled(num, radix) {
+                return None;
+            }
+            2
+        }
+        Repeats::AtLeastTwice => {
+            if !is_repeated(num, radix) {
+                return None;
+            }
+            // The shortest pattern has the most repeats.
+            (2..=num_digits(num, radix)).rev().find(|&repeats| has_repeats(num, radix, repeats))?
+        }
+    };
+
+    let digits = to_radix_string(num, radix);
+    let unit = digits[..digits.len() / repeats as usize].to_string();
+    Some(InvalidId { id: num, unit, repeats })
+}
+
+//
+// Format `num` in base `radix`, using lower case letters for digits above 9.
+//
+fn to_radix_string(mut num: u128, radix: u32) -> String {
+    let mut digits = Vec::new();
+    loop {
+        let digit = (num % u128::from(radix)) as u32;
+        digits.push(char::from_digit(digit, radix).unwrap());
+        num /= u128::from(radix);
+        if num == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+//
+// Options controlling how the list of ranges is parsed.
+//
+// `normalize_reversed`: treat a range like `22-11` as `11-22` instead of
+// rejecting it.
+//
+// `keep_duplicates`: don't merge overlapping ranges, so that an ID in
+// several ranges is counted once per range (the original behavior).
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub radix: u32,
+    pub normalize_reversed: bool,
+    pub keep_duplicates: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions { radix: 10, normalize_reversed: false, keep_duplicates: false }
+    }
+}
+
+//
+// Problems with the input.  `index` is the (zero-based) position of the
+// offending range within the input.  `InvalidRadix` means the options
+// asked for a base outside 2 through 36.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidRadix { radix: u32 },
+    EmptyRange { index: usize },
+    MissingDash { index: usize, text: String },
+    InvalidNumber { index: usize, text: String, error: ParseIntError },
+    Reversed { index: usize, text: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidRadix { radix } =>
+                write!(f, "radix {radix} is not between 2 and 36"),
+            ParseError::EmptyRange { index } =>
+                write!(f, "range {index}: empty range"),
+            ParseError::MissingDash { index, text } =>
+                write!(f, "range {index}: expected START-END, found {text:?}"),
+            ParseError::InvalidNumber { index, text, error } =>
+                write!(f, "range {index}: invalid number {text:?}: {error}"),
+            ParseError::Reversed { index, text } =>
+                write!(f, "range {index}: start is greater than end in {text:?}"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+//
+// Parse the input with the default options (decimal, no reversed ranges,
+// overlapping ranges merged), panicking if the input is invalid.
+//
+fn parse_input(input: &str, radix: u32) -> Vec<RangeInclusive<u128>> {
+    parse_ranges(input, &ParseOptions { radix, ..ParseOptions::default() })
+        .expect("Invalid input")
+}
+
+//
+// Parse a list of ranges like `11-22,95-115`.  Ranges are separated by
+// commas and/or whitespace (including newlines), and there may be a
+// trailing comma.  Unless `options.keep_duplicates` is set, overlapping
+// ranges are merged, so the result is sorted and every ID appears once.
+//
+pub fn parse_ranges(input: &str, options: &ParseOptions) -> Result<Vec<RangeInclusive<u128>>, ParseError> {
+    if !(2..=36).contains(&options.radix) {
+        return Err(ParseError::InvalidRadix { radix: options.radix });
+    }
+
+    let mut ranges = Vec::new();
+    let mut segments = input.split(',').peekable();
+    while let Some(segment) = segments.next() {
+        let mut words = segment.split_whitespace().peekable();
+        if words.peek().is_none() {
+            if segments.peek().is_none() && !ranges.is_empty() {
+                // Trailing comma (or an input with only whitespace after it)
+                break;
+            }
+            return Err(ParseError::EmptyRange { index: ranges.len() });
+        }
+        for word in words {
+            ranges.push(parse_range(word, ranges.len(), options)?);
+        }
+    }
+
+    if !options.keep_duplicates {
+        ranges = merge_ranges(ranges);
+    }
+
+    Ok(ranges)
+}
+
+fn parse_range(text: &str, index: usize, options: &ParseOptions) -> Result<RangeInclusive<u128>, ParseError> {
+    let Some((start, end)) = text.split_once('-') else {
+        return Err(ParseError::MissingDash { index, text: text.to_string() });
+    };
+    let parse_num = |num_str: &str| {
+        // `from_str_radix` accepts a leading `+`, but IDs are plain digits.
+        // Parsing the sign on its own gives the same "invalid digit" error
+        // as any other stray character.
+        let digits = if num_str.starts_with('+') { "+" } else { num_str };
+        u128::from_str_radix(digits, options.radix).map_err(|error| {
+            ParseError::InvalidNumber { index, text: num_str.to_string(), error }
+        })
+    };
+    let (start, end) = (parse_num(start)?, parse_num(end)?);
+
+    if start > end {
+        if !options.normalize_reversed {
+            return Err(ParseError::Reversed { index, text: text.to_string() });
+        }
+        return Ok(end ..= start);
+    }
+    Ok(start ..= end)
+}
+
+//
+// Sort the ranges, and combine any that overlap, so that each ID is in at
+// most one range.
+//
+pub fn merge_ranges(mut ranges: Vec<RangeInclusive<u128>>) -> Vec<RangeInclusive<u128>> {
+    ranges.sort_unstable_by_key(|range| *range.start());
+    ranges.into_iter()
+        .coalesce(|first, second| {
+            if second.start() <= first.end() {
+                Ok(*first.start() ..= *first.end().max(second.end()))
+            } else {
+                Err((first, second))
+            }
         })
         .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, part1_radix, part2_radix, is_doubled, is_repeated, has_repeats};
+    use super::{report, report_merged, report_ranges, invalid_id, Repeats};
+    use super::{parse_ranges, ParseOptions, ParseError};
     
     static EXAMPLE_INPUT: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
     static FULL_INPUT: &str = include_str!("../input.txt");
@@ -100,4 +471,144 @@ mod tests {
     #[test]
     fn test_part2_full() {
         assert_eq!(part2(FULL_INPUT), 36037497037);
-    }}
+    }
+
+    #[test]
+    fn test_fast_path_matches_general() {
+        for num in (0..1_000_000).chain(1_000_000_000..1_000_100_000).chain([9999999999, 1212121212, 1234512345]) {
+            assert_eq!(is_doubled(num, 10), has_repeats(num, 10, 2), "num={num}");
+            assert_eq!(
+                is_repeated(num, 10),
+                (2..=10).any(|repeats| has_repeats(num, 10, repeats)),
+                "num={num}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_more_than_10_digits() {
+        // "123123" x2, and "123" x4
+        assert_eq!(part1("123123123123-123123123123"), 123123123123);
+        assert_eq!(part2("123123123123-123123123123"), 123123123123);
+
+        // "123" x3 only counts for part 2
+        assert_eq!(part1("123123122-123123124"), 0);
+        assert_eq!(part2("123123122-123123124"), 123123123);
+
+        // 38 and 39 digits, near the limit of u128
+        let big = "123456789012345678901234567890123456789";
+        assert!(!is_repeated(big.parse().unwrap(), 10));
+        let doubled = "1234567890123456789".repeat(2).parse().unwrap();
+        assert!(is_doubled(doubled, 10));
+        assert!(is_repeated(doubled, 10));
+        assert!(!is_repeated(u128::MAX, 10));
+        assert!(!is_repeated(u128::MAX - 1, 10));
+    }
+
+    #[test]
+    fn test_hex() {
+        // 0xabab is doubled, 0xabac is not; 0xabcabc is doubled, 0xaaa is tripled
+        assert_eq!(part1_radix("abab-abac", 16), 0xabab);
+        assert_eq!(part1_radix("abcabc-abcabc,aaa-aaa", 16), 0xabcabc);
+        assert_eq!(part2_radix("abcabc-abcabc,aaa-aaa", 16), 0xabcabc + 0xaaa);
+        assert!(is_repeated(u128::MAX, 16));
+        assert!(is_doubled(u128::MAX, 2));
+    }
+
+    #[test]
+    fn test_report_example() {
+        let part1_report = report(EXAMPLE_INPUT, 10, Repeats::Twice);
+        assert_eq!(part1_report.len(), 11);
+        assert_eq!(part1_report.iter().map(|r| r.sum()).sum::<u128>(), 1227775554);
+        assert_eq!(part1_report.iter().map(|r| r.count()).sum::<usize>(), 8);
+        assert_eq!(part1_report[3].invalid.len(), 1);
+        assert_eq!(part1_report[3].invalid[0].to_string(), "1188511885 = \"11885\" x2");
+        assert_eq!(part1_report[3].to_string(), "\
+1188511880-1188511890: 1 invalid, sum 1188511885
+    1188511885 = \"11885\" x2
+");
+
+        let part2_report = report(EXAMPLE_INPUT, 10, Repeats::AtLeastTwice);
+        assert_eq!(part2_report.iter().map(|r| r.sum()).sum::<u128>(), 4174379265);
+        assert_eq!(part2_report.iter().map(|r| r.count()).sum::<usize>(), 13);
+        let ids: Vec<String> = part2_report[1].invalid.iter().map(|i| i.to_string()).collect();
+        assert_eq!(ids, ["99 = \"9\" x2", "111 = \"1\" x3"]);
+    }
+
+    #[test]
+    fn test_invalid_id_shortest_unit() {
+        let invalid = invalid_id(222222, 10, Repeats::AtLeastTwice).unwrap();
+        assert_eq!((invalid.unit.as_str(), invalid.repeats), ("2", 6));
+        let invalid = invalid_id(222222, 10, Repeats::Twice).unwrap();
+        assert_eq!((invalid.unit.as_str(), invalid.repeats), ("222", 2));
+        let invalid = invalid_id(0xabab, 16, Repeats::Twice).unwrap();
+        assert_eq!(invalid.to_string(), "abab = \"ab\" x2");
+        assert_eq!(invalid_id(123, 10, Repeats::AtLeastTwice), None);
+    }
+
+    #[test]
+    fn test_parse_whitespace() {
+        let ranges = parse_ranges(" 11-22,\n95-115\n998-1012 , 222220-222224,\n", &ParseOptions::default());
+        assert_eq!(ranges, Ok(vec![11..=22, 95..=115, 998..=1012, 222220..=222224]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let options = ParseOptions::default();
+        assert_eq!(parse_ranges("", &options), Err(ParseError::EmptyRange { index: 0 }));
+        assert_eq!(parse_ranges("11-22,,95-115", &options), Err(ParseError::EmptyRange { index: 1 }));
+        assert_eq!(parse_ranges("11-22,95", &options),
+            Err(ParseError::MissingDash { index: 1, text: "95".to_string() }));
+        assert!(matches!(parse_ranges("11-22,95-x", &options),
+            Err(ParseError::InvalidNumber { index: 1, ref text, .. }) if text == "x"));
+        assert!(matches!(parse_ranges("+5-+9", &options),
+            Err(ParseError::InvalidNumber { index: 0, ref text, .. }) if text == "+5"));
+        assert!(matches!(parse_ranges("5--9", &options),
+            Err(ParseError::InvalidNumber { index: 0, ref text, .. }) if text == "-9"));
+        assert!(matches!(parse_ranges("11-", &options),
+            Err(ParseError::InvalidNumber { index: 0, ref text, .. }) if text.is_empty()));
+        let error = parse_ranges("11-22,95-115,22-11", &options).unwrap_err();
+        assert_eq!(error, ParseError::Reversed { index: 2, text: "22-11".to_string() });
+        assert_eq!(error.to_string(), "range 2: start is greater than end in \"22-11\"");
+    }
+
+    #[test]
+    fn test_parse_invalid_radix() {
+        for radix in [0, 1, 37] {
+            let options = ParseOptions { radix, ..ParseOptions::default() };
+            assert_eq!(parse_ranges("1-2", &options), Err(ParseError::InvalidRadix { radix }));
+        }
+        let error = parse_ranges("1-2", &ParseOptions { radix: 37, ..ParseOptions::default() }).unwrap_err();
+        assert_eq!(error.to_string(), "radix 37 is not between 2 and 36");
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidRadix { radix: 37 }")]
+    fn test_part1_invalid_radix() {
+        part1_radix("1-2", 37);
+    }
+
+    #[test]
+    fn test_parse_reversed() {
+        let options = ParseOptions { normalize_reversed: true, ..ParseOptions::default() };
+        assert_eq!(parse_ranges("22-11,115-95", &options), Ok(vec![11..=22, 95..=115]));
+    }
+
+    #[test]
+    fn test_parse_overlaps() {
+        let input = "95-115,11-22,100-120,22-33";
+        assert_eq!(parse_ranges(input, &ParseOptions::default()), Ok(vec![11..=33, 95..=120]));
+
+        let options = ParseOptions { keep_duplicates: true, ..ParseOptions::default() };
+        let ranges = parse_ranges(input, &options).unwrap();
+        assert_eq!(ranges, vec![95..=115, 11..=22, 100..=120, 22..=33]);
+
+        // 22 and 111 are each in two ranges
+        let merged: u128 = report_merged(input, 10, Repeats::AtLeastTwice).iter().map(|r| r.sum()).sum();
+        let duplicated: u128 = report_ranges(ranges, 10, Repeats::AtLeastTwice).iter().map(|r| r.sum()).sum();
+        assert_eq!(report(input, 10, Repeats::AtLeastTwice).iter().map(|r| r.range.clone()).collect::<Vec<_>>(),
+            vec![95..=115, 11..=22, 100..=120, 22..=33]);
+        assert_eq!(merged, 11 + 22 + 33 + 99 + 111);
+        assert_eq!(duplicated, merged + 22 + 111);
+    }
+}
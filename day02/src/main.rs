@@ -1,4 +1,4 @@
-use day02::{part1, part2};
+use day02::{part1, part2, report, report_merged, Repeats};
 
 fn main() {
     let input = include_str!("../input.txt");
@@ -8,4 +8,18 @@ fn main() {
 
     let result2 = part2(input);
     println!("Part 2: {result2}");
+
+    // With `--report`, list the invalid IDs in each input range, for both
+    // parts.  With `--report-merged`, merge overlapping ranges first.
+    let merged = std::env::args().any(|arg| arg == "--report-merged");
+    if merged || std::env::args().any(|arg| arg == "--report") {
+        let report = if merged { report_merged } else { report };
+        for (part, rule) in [(1, Repeats::Twice), (2, Repeats::AtLeastTwice)] {
+            println!();
+            println!("Part {part} invalid IDs:");
+            for range_report in report(input, 10, rule) {
+                print!("{range_report}");
+            }
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use day03::{ part1, part2 };
+use day03::{ part1, part2, largest_subsequence, largest_subsequence_rescan };
 
 fn main() {
     divan::main();
@@ -15,3 +15,33 @@ fn bench_part1() {
 fn bench_part2() {
     part2(INPUT);
 }
+
+// Length of the generated lines for the long line benchmarks
+const LONG_LINE: usize = 20_000;
+
+// A long line of pseudo-random digits, the same on every run.
+fn long_line() -> Vec<u8> {
+    let mut state: u32 = 12345;
+    (0..LONG_LINE).map(|_| {
+        state = state.wrapping_mul(1103515245).wrapping_add(12345);
+        b'0' + ((state >> 16) % 10) as u8
+    }).collect()
+}
+
+#[divan::bench(args = [2, 12, 100, 1000])]
+fn bench_long_line_stack(bencher: divan::Bencher, digits: usize) {
+    let line = long_line();
+    bencher.bench(|| largest_subsequence(divan::black_box(&line), digits));
+}
+
+#[divan::bench(args = [2, 12, 100, 1000])]
+fn bench_long_line_rescan(bencher: divan::Bencher, digits: usize) {
+    let line = long_line();
+    bencher.bench(|| largest_subsequence_rescan(divan::black_box(&line), digits));
+}
+
+#[divan::bench(args = [LONG_LINE / 2, LONG_LINE - 1])]
+fn bench_many_digits_stack(bencher: divan::Bencher, digits: usize) {
+    let line = long_line();
+    bencher.bench(|| largest_subsequence(divan::black_box(&line), digits));
+}
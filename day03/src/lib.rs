@@ -1,64 +1,286 @@
+use std::error::Error;
+use std::fmt;
+
 pub fn part1(input: &str) -> u64 {
-    input.lines().map(|line| {
-        largest_num(line, 2)
-    }).sum::<u64>()
+    total_joltage(input, 2, false).expect("Invalid input")
 }
 
 pub fn part2(input: &str) -> u64 {
-    input.lines().map(|line| {
-        largest_num(line, 12)
-    }).sum::<u64>()
+    total_joltage(input, 12, false).expect("Invalid input")
+}
+
+//
+// Sum the largest `digits`-digit numbers from each bank in the input.
+// With `lenient`, blank lines are skipped and whitespace is ignored.
+//
+pub fn total_joltage(input: &str, digits: usize, lenient: bool) -> Result<u64, BankError> {
+    let mut total: u64 = 0;
+    for bank in parse_banks(input, lenient)? {
+        let Some(joltage) = largest_num(&bank.digits, digits) else {
+            return Err(BankError::TooShort { line: bank.line, length: bank.digits.len(), digits });
+        };
+        total = joltage
+            .and_then(|joltage| total.checked_add(joltage))
+            .ok_or(BankError::Overflow { line: bank.line })?;
+    }
+    Ok(total)
+}
+
+//
+// A validated line of the input.  `digits` contains only ASCII digits.
+// `line` is the (one-based) line number within the input.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bank {
+    pub line: usize,
+    pub digits: Vec<u8>,
+}
+
+//
+// Problems with a bank.  Line and column numbers are one-based, and
+// columns count characters within the original line.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BankError {
+    BlankLine { line: usize },
+    NotADigit { line: usize, column: usize, found: char },
+    TooShort { line: usize, length: usize, digits: usize },
+    // The bank's joltage, or the total up to it, doesn't fit in a `u64`
+    Overflow { line: usize },
+}
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::BlankLine { line } =>
+                write!(f, "line {line}: blank line"),
+            BankError::NotADigit { line, column, found } =>
+                write!(f, "line {line}, column {column}: expected a digit, found {found:?}"),
+            BankError::TooShort { line, length, digits } =>
+                write!(f, "line {line}: bank has {length} digits, but {digits} are needed"),
+            BankError::Overflow { line } =>
+                write!(f, "line {line}: joltage is too large"),
+        }
+    }
+}
+
+impl Error for BankError {}
+
+//
+// Parse and validate the banks in the input, one per line.  Normally,
+// every line must contain only digits.  With `lenient`, blank lines are
+// skipped and whitespace anywhere in a line is removed.
+//
+pub fn parse_banks(input: &str, lenient: bool) -> Result<Vec<Bank>, BankError> {
+    let mut banks = Vec::new();
+    for (index, text) in input.lines().enumerate() {
+        let line = index + 1;
+        let mut digits = Vec::with_capacity(text.len());
+        for (column, ch) in text.chars().enumerate() {
+            if ch.is_ascii_digit() {
+                digits.push(ch as u8);
+            } else if !(lenient && ch.is_whitespace()) {
+                return Err(BankError::NotADigit { line, column: column + 1, found: ch });
+            }
+        }
+
+        if digits.is_empty() {
+            if lenient {
+                continue;
+            }
+            return Err(BankError::BlankLine { line });
+        }
+        banks.push(Bank { line, digits });
+    }
+    Ok(banks)
 }
 
 //
 // Return the largest number constructed from `digits` digits within
-// `line`, in the order they appear within `line`.
+// `bank`, in the order they appear within `bank`.  The outer `None` means
+// `bank` is too short; the inner one that the number is too large.
 //
-// TODO: Try iterating over the characters within `line`, and keep the
-// best answer so far.  Start with the first `digits` characters of `line`.
-// Then for each following character, if it is larger than any of the
-// characters remembered so far, remove the smallest remembered character
-// and append the new character.  This would require only one pass over
-// the characters in `line`.
+fn largest_num(bank: &[u8], digits: usize) -> Option<Option<u64>> {
+    largest_subsequence(bank, digits).map(|bytes| to_number(&bytes))
+}
+
+//
+// Return the largest subsequence of `digits` bytes within `bytes`, or
+// `None` if `bytes` is shorter than `digits`.
+//
+pub fn largest_subsequence(bytes: &[u8], digits: usize) -> Option<Vec<u8>> {
+    if digits > bytes.len() {
+        return None;
+    }
+    let result = subsequence_indices(bytes, digits, |byte, top| byte > top)
+        .into_iter()
+        .map(|index| bytes[index])
+        .collect();
+    Some(result)
+}
+
+//
+// Return the indices of the best subsequence of `digits` bytes within
+// `bytes`, where `better(new, old)` says whether byte `new` is better than
+// byte `old` in the same position.
+//
+// This makes a single pass over `bytes`, keeping a stack of the best
+// answer so far.  For each new byte, pop any worse bytes off the top
+// of the stack (they would be better replaced by the new byte), as long
+// as there are enough bytes left to fill the result.  This is O(n),
+// independent of `digits`.
+//
+fn subsequence_indices(bytes: &[u8], digits: usize, better: impl Fn(u8, u8) -> bool) -> Vec<usize> {
+    assert!(digits <= bytes.len(), "Selecting {digits} of {} bytes", bytes.len());
+
+    // How many more bytes we can afford to leave out of the result
+    let mut discards = bytes.len() - digits;
+    let mut stack: Vec<usize> = Vec::with_capacity(digits);
+
+    for (index, &byte) in bytes.iter().enumerate() {
+        while discards > 0 && stack.last().is_some_and(|&top| better(byte, bytes[top])) {
+            stack.pop();
+            discards -= 1;
+        }
+        if stack.len() < digits {
+            stack.push(index);
+        } else {
+            // No room for `byte`, and it's no better than what we have
+            discards -= 1;
+        }
+    }
+
+    stack
+}
+
+//
+// Which subsequence of digits to select from a line.
 //
-fn largest_num(line: &str, digits: usize) -> u64 {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Select {
+    Largest,
+    Smallest,
+    // The smallest whose first digit is not zero
+    SmallestNoLeadingZero,
+}
+
+//
+// The digits selected from a line: their (byte) indices within the line,
+// and the resulting number as a string of digits.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub indices: Vec<usize>,
+    pub digits: String,
+}
+
+impl Selection {
+    //
+    // The selected digits as a number, or `None` if it doesn't fit in a
+    // `u64` (which can happen when selecting more than 19 digits), or if
+    // any of the selected characters isn't a digit.
+    //
+    pub fn value(&self) -> Option<u64> {
+        self.digits.chars().try_fold(0u64, |result, ch| {
+            result.checked_mul(10)?.checked_add(ch.to_digit(10)? as u64)
+        })
+    }
+}
+
+//
+// Select `digits` digits from `line`, in the order they appear within
+// `line`, to form the largest or smallest number.  Returns `None` if
+// `line` has fewer than `digits` characters, or if there is no way to
+// avoid a leading zero for `Select::SmallestNoLeadingZero`.
+//
+pub fn select_digits(line: &str, digits: usize, select: Select) -> Option<Selection> {
     let bytes = line.as_bytes();
+    if digits > bytes.len() {
+        return None;
+    }
+
+    let indices = match select {
+        Select::Largest => subsequence_indices(bytes, digits, |byte, top| byte > top),
+        Select::Smallest => subsequence_indices(bytes, digits, |byte, top| byte < top),
+        Select::SmallestNoLeadingZero if digits == 0 => Vec::new(),
+        Select::SmallestNoLeadingZero => {
+            // The first digit is the smallest non-zero digit that leaves
+            // enough digits after it.  The rest is the smallest selection
+            // from the remainder of the line.
+            let first = bytes[..=bytes.len() - digits]
+                .iter()
+                .enumerate()
+                .filter(|&(_, &byte)| byte != b'0')
+                .min_by_key(|&(index, &byte)| (byte, index))?
+                .0;
+            let rest = &bytes[first+1..];
+            std::iter::once(first)
+                .chain(subsequence_indices(rest, digits - 1, |byte, top| byte < top)
+                    .into_iter()
+                    .map(|index| index + first + 1))
+                .collect()
+        }
+    };
+
+    let digits = indices.iter().map(|&index| bytes[index] as char).collect();
+    Some(Selection { indices, digits })
+}
+
+//
+// The original version of `largest_subsequence`.  For each output byte,
+// it rescans the window of bytes that could come next, so it is O(n*k).
+// Kept as a reference for testing and benchmarking.
+//
+pub fn largest_subsequence_rescan(bytes: &[u8], digits: usize) -> Option<Vec<u8>> {
+    if digits > bytes.len() {
+        return None;
+    }
     let length = bytes.len();
-    let mut result = 0;
+    let mut result = Vec::with_capacity(digits);
     let mut start = 0;
 
     for remaining in (0..digits).rev() {
         // Find the largest digit of `bytes` at offset `start_offset` or greater,
         // and not considering the last `remaining` values.
         let (index, byte) = find_largest(bytes, start, length-remaining);
-        result = result * 10 + (byte - b'0') as u64;
+        result.push(byte);
         start = index + 1;
     }
 
-    result
+    Some(result)
 }
 
 // Find the largest byte with index in start..end.  Return the index and byte.
 // If the maximum value occurs multiple times, return the lowest such index.
 fn find_largest(bytes: &[u8], start: usize, end: usize) -> (usize, u8) {
-    debug_assert!(start < end);
+    assert!(start < end && end <= bytes.len(), "Empty or out of bounds range {start}..{end}");
 
     let mut largest_index = start;
     let mut largest_value = bytes[start];
 
-    for index in (start+1)..end {
-        if bytes[index] > largest_value {
+    for (index, &byte) in bytes.iter().enumerate().take(end).skip(start+1) {
+        if byte > largest_value {
             largest_index = index;
-            largest_value = bytes[index];
+            largest_value = byte;
         }
     }
     
     (largest_index, largest_value)
 }
 
+// Convert a sequence of ASCII digits to a number, or `None` if it
+// doesn't fit in a `u64`.
+fn to_number(bytes: &[u8]) -> Option<u64> {
+    bytes.iter().try_fold(0u64, |result, byte| {
+        result.checked_mul(10)?.checked_add((byte - b'0') as u64)
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, largest_subsequence, largest_subsequence_rescan};
+    use super::{select_digits, Select};
+    use super::{total_joltage, parse_banks, Bank, BankError};
     
     static EXAMPLE_INPUT: &str = "\
 987654321111111
@@ -87,4 +309,115 @@ mod tests {
     fn test_part2_full() {
         assert_eq!(part2(FULL_INPUT), 170418192256861);
     }
+
+    #[test]
+    fn test_stack_matches_rescan() {
+        for line in EXAMPLE_INPUT.lines().chain(["9", "1111", "0000", "12121212", "9876598765", "1299921"]) {
+            for digits in 1..=line.len() {
+                assert_eq!(
+                    largest_subsequence(line.as_bytes(), digits),
+                    largest_subsequence_rescan(line.as_bytes(), digits),
+                    "line={line} digits={digits}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_largest() {
+        let selection = select_digits("818181911112111", 12, Select::Largest).unwrap();
+        assert_eq!(selection.digits, "888911112111");
+        assert_eq!(selection.indices, [0, 2, 4, 6, 7, 8, 9, 10, 11, 12, 13, 14]);
+        assert_eq!(selection.value(), Some(888911112111));
+
+        let selection = select_digits("234234234234278", 2, Select::Largest).unwrap();
+        assert_eq!((selection.indices.as_slice(), selection.digits.as_str()), (&[13, 14][..], "78"));
+    }
+
+    #[test]
+    fn test_select_smallest() {
+        let selection = select_digits("3102030", 3, Select::Smallest).unwrap();
+        assert_eq!((selection.indices.as_slice(), selection.digits.as_str()), (&[2, 4, 6][..], "000"));
+        assert_eq!(selection.value(), Some(0));
+
+        let selection = select_digits("3102030", 3, Select::SmallestNoLeadingZero).unwrap();
+        assert_eq!((selection.indices.as_slice(), selection.digits.as_str()), (&[1, 2, 4][..], "100"));
+
+        let selection = select_digits("3102030", 4, Select::SmallestNoLeadingZero).unwrap();
+        assert_eq!(selection.digits, "1000");
+
+        // Every 3-digit choice from "0005" starts with zero
+        assert_eq!(select_digits("0005", 3, Select::SmallestNoLeadingZero), None);
+        assert_eq!(select_digits("0005", 1, Select::SmallestNoLeadingZero).unwrap().digits, "5");
+    }
+
+    #[test]
+    fn test_select_many_digits() {
+        let line = "98765432109876543210987654321";
+        assert_eq!(select_digits(line, 30, Select::Largest), None);
+
+        let selection = select_digits(line, 25, Select::Largest).unwrap();
+        assert_eq!(selection.digits, "9876549876543210987654321");
+        assert_eq!(selection.value(), None);
+
+        let selection = select_digits(line, 19, Select::Largest).unwrap();
+        assert_eq!(selection.value(), Some(9987654321987654321));
+    }
+
+    #[test]
+    fn test_not_a_digit() {
+        let error = total_joltage("987654321111111\n81111x111111119\n", 2, false);
+        assert_eq!(error, Err(BankError::NotADigit { line: 2, column: 6, found: 'x' }));
+        assert_eq!(error.unwrap_err().to_string(), "line 2, column 6: expected a digit, found 'x'");
+
+        // Lenient mode only forgives whitespace
+        let error = total_joltage("987654321111111\n81111x111111119\n", 2, true);
+        assert_eq!(error, Err(BankError::NotADigit { line: 2, column: 6, found: 'x' }));
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let input = "98765 4321111111\n  811111111111119\t\n";
+        assert_eq!(total_joltage(input, 2, false),
+            Err(BankError::NotADigit { line: 1, column: 6, found: ' ' }));
+        assert_eq!(total_joltage(input, 2, true), Ok(98 + 89));
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        let input = "987654321111111\n\n811111111111119\n   \n";
+        assert_eq!(total_joltage(input, 2, false), Err(BankError::BlankLine { line: 2 }));
+        assert_eq!(parse_banks(input, true), Ok(vec![
+            Bank { line: 1, digits: b"987654321111111".to_vec() },
+            Bank { line: 3, digits: b"811111111111119".to_vec() },
+        ]));
+        assert_eq!(total_joltage(input, 2, true), Ok(98 + 89));
+    }
+
+    #[test]
+    fn test_too_short() {
+        let input = "987654321111111\n81111\n";
+        assert_eq!(total_joltage(input, 5, false), Ok(98765 + 81111));
+        let error = total_joltage(input, 12, false);
+        assert_eq!(error, Err(BankError::TooShort { line: 2, length: 5, digits: 12 }));
+        assert_eq!(error.unwrap_err().to_string(), "line 2: bank has 5 digits, but 12 are needed");
+
+        assert_eq!(largest_subsequence(b"81111", 6), None);
+        assert_eq!(largest_subsequence_rescan(b"81111", 6), None);
+        assert_eq!(largest_subsequence(b"81111", 5), Some(b"81111".to_vec()));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let error = total_joltage("987654321111111\n1234567890123456789012345\n", 25, false);
+        assert_eq!(error, Err(BankError::TooShort { line: 1, length: 15, digits: 25 }));
+        let error = total_joltage("1234567890123456789012345\n", 25, false);
+        assert_eq!(error, Err(BankError::Overflow { line: 1 }));
+        assert_eq!(error.unwrap_err().to_string(), "line 1: joltage is too large");
+
+        // Each bank fits, but the total doesn't
+        assert_eq!(total_joltage("99999999999999999999\n", 19, false), Ok(9999999999999999999));
+        let input = "9999999999999999999\n9999999999999999999\n";
+        assert_eq!(total_joltage(input, 19, false), Err(BankError::Overflow { line: 2 }));
+    }
 }
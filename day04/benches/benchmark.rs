@@ -1,4 +1,4 @@
-use day04::{ part1, part2, parse_input, part2_vec };
+use day04::{ part1_set, part2_set, parse_input, part2_vec, bits, generated_grid };
 
 fn main() {
     divan::main();
@@ -12,13 +12,13 @@ fn bench_parse() {
 }
 
 #[divan::bench]
-fn bench_part1() {
-    part1(INPUT);
+fn bench_part1_set() {
+    part1_set(INPUT);
 }
 
 #[divan::bench]
-fn bench_part2() {
-    part2(INPUT);
+fn bench_part2_set() {
+    part2_set(INPUT);
 }
 
 #[divan::bench]
@@ -30,3 +30,63 @@ fn bench_part2_vec_parse() {
 fn bench_part2_vec() {
     part2_vec::part2_vec(INPUT);
 }
+
+#[divan::bench]
+fn bench_part2_worklist() {
+    part2_vec::part2_worklist(INPUT);
+}
+
+#[divan::bench]
+fn bench_bits_parse() {
+    bits::parse_input(INPUT);
+}
+
+#[divan::bench]
+fn bench_part1_bits() {
+    bits::part1_bits(INPUT);
+}
+
+#[divan::bench]
+fn bench_part2_bits() {
+    bits::part2_bits(INPUT);
+}
+
+// Sizes (width and height) of the generated grids
+const SIZES: [usize; 3] = [200, 500, 1000];
+
+// The HashSet version is slow on large grids, so take fewer samples
+#[divan::bench(args = SIZES, sample_count = 10)]
+fn bench_large_part2_set(bencher: divan::Bencher, size: usize) {
+    let input = generated_grid(size, size);
+    bencher.bench(|| part2_set(&input));
+}
+
+#[divan::bench(args = SIZES)]
+fn bench_large_part2_vec(bencher: divan::Bencher, size: usize) {
+    let input = generated_grid(size, size);
+    bencher.bench(|| part2_vec::part2_vec(&input));
+}
+
+#[divan::bench(args = SIZES)]
+fn bench_large_part2_worklist(bencher: divan::Bencher, size: usize) {
+    let input = generated_grid(size, size);
+    bencher.bench(|| part2_vec::part2_worklist(&input));
+}
+
+#[divan::bench(args = SIZES)]
+fn bench_large_part2_bits(bencher: divan::Bencher, size: usize) {
+    let input = generated_grid(size, size);
+    bencher.bench(|| bits::part2_bits(&input));
+}
+
+#[divan::bench(args = [1000, 2000, 4000])]
+fn bench_huge_part2_bits(bencher: divan::Bencher, size: usize) {
+    let input = generated_grid(size, size);
+    bencher.bench(|| bits::part2_bits(&input));
+}
+
+#[divan::bench(args = [1000, 2000, 4000])]
+fn bench_huge_part2_worklist(bencher: divan::Bencher, size: usize) {
+    let input = generated_grid(size, size);
+    bencher.bench(|| part2_vec::part2_worklist(&input));
+}
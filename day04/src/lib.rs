@@ -2,7 +2,26 @@ use rustc_hash::FxHashSet as HashSet;
 
 // TODO: Instead of a HashSet, should I use a 2D array/grid?
 
+//
+// `part1` and `part2` use the bit-packed grid from the `bits` module.
+// Everything except the `automaton` module is hard-coded to the puzzle's
+// rule: a roll can be removed when fewer than 4 of its 8 surrounding
+// cells hold rolls, and the grid doesn't wrap.  The `automaton` module is
+// the general engine, with configurable neighborhoods, comparisons and
+// wrapping; with `Rule::default()`, it gives the same answers as these.
+//
 pub fn part1(input: &str) -> usize {
+    bits::part1_bits(input)
+}
+
+pub fn part2(input: &str) -> usize {
+    bits::part2_bits(input)
+}
+
+//
+// The original solutions, storing the rolls' positions in a set.
+//
+pub fn part1_set(input: &str) -> usize {
     let mut rolls: HashSet<(i16, i16)> = HashSet::default();
     for (row, line) in input.lines().enumerate() {
         for (col, ch) in line.chars().enumerate() {
@@ -19,7 +38,7 @@ pub fn part1(input: &str) -> usize {
     }).count()
 }
 
-pub fn part2(input: &str) -> usize {
+pub fn part2_set(input: &str) -> usize {
     let mut rolls: HashSet<(i16, i16)> = HashSet::default();
     for (row, line) in input.lines().enumerate() {
         for (col, ch) in line.chars().enumerate() {
@@ -48,7 +67,7 @@ pub fn part2(input: &str) -> usize {
         // eprintln!("Removing {} rolls", removable.len());
 
         for roll in removable.iter() {
-            rolls.remove(&roll);
+            rolls.remove(roll);
         }
     }
 
@@ -84,6 +103,8 @@ pub mod part2_vec {
             self.occupied.iter().filter(|item| **item).count()
         }
 
+        // Always the 8 surrounding cells (the puzzle's rule); the padding
+        // around the grid means the edges don't need special cases.
         fn count_neighbors(&self, row: usize, col: usize) -> u8 {
             self[(row-1, col-1)] as u8 +
             self[(row-1, col  )] as u8 +
@@ -103,7 +124,7 @@ pub mod part2_vec {
             let (row, col) = index;
             debug_assert!(row < self.rows);
             debug_assert!(col < self.columns);
-            &self.occupied[(row * self.columns + col) as usize]
+            &self.occupied[row * self.columns + col]
         }
     }
 
@@ -112,13 +133,13 @@ pub mod part2_vec {
             let (row, col) = index;
             debug_assert!(row < self.rows);
             debug_assert!(col < self.columns);
-            &mut self.occupied[(row * self.columns + col) as usize]
+            &mut self.occupied[row * self.columns + col]
         }
     }
 
     pub fn parse_input(input: &str) -> Rolls {
-        let columns = (input.lines().next().unwrap().len() + 2) as usize;
-        let rows = (input.lines().count() + 2) as usize;
+        let columns = input.lines().next().unwrap().len() + 2;
+        let rows = input.lines().count() + 2;
         let mut occupied = Vec::new();
         occupied.resize(rows * columns, false);
         let mut rolls = Rolls { occupied, columns, rows };
@@ -158,9 +179,68 @@ pub mod part2_vec {
         initial_count - rolls.len()
     }
 
+    //
+    // Like `part2_vec`, but instead of rescanning every roll on each pass,
+    // keep a count of each roll's neighbors, and a queue of rolls to be
+    // removed.  Removing a roll decrements its neighbors' counts, and any
+    // neighbor whose count drops below 4 joins the queue.  Each roll is
+    // queued at most once, so this is linear in the number of rolls.
+    //
+    // Since removing rolls can only make other rolls removable (never the
+    // reverse), the order of removal doesn't change which rolls remain.
+    //
+    pub fn part2_worklist(input: &str) -> usize {
+        let mut rolls = parse_input(input);
+        let columns = rolls.columns;
+
+        // Offsets to the 8 neighbors in the flattened (padded) grid
+        let offsets = [
+            -(columns as isize) - 1, -(columns as isize), -(columns as isize) + 1,
+            -1, 1,
+            columns as isize - 1, columns as isize, columns as isize + 1,
+        ];
+
+        let mut neighbors = vec![0u8; rolls.occupied.len()];
+        let mut queue = Vec::new();
+        for row in 1..(rolls.rows-1) {
+            for col in 1..(rolls.columns-1) {
+                if rolls[(row, col)] {
+                    let index = row * columns + col;
+                    neighbors[index] = rolls.count_neighbors(row, col);
+                    if neighbors[index] < 4 {
+                        queue.push(index);
+                    }
+                }
+            }
+        }
+
+        // A roll is marked as unoccupied as soon as it is queued, so that
+        // it isn't queued again.
+        for &index in queue.iter() {
+            rolls.occupied[index] = false;
+        }
+
+        let mut removed = 0;
+        while let Some(index) = queue.pop() {
+            removed += 1;
+            for offset in offsets {
+                let neighbor = index.wrapping_add_signed(offset);
+                if rolls.occupied[neighbor] {
+                    neighbors[neighbor] -= 1;
+                    if neighbors[neighbor] < 4 {
+                        rolls.occupied[neighbor] = false;
+                        queue.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        removed
+    }
+
     #[cfg(test)]
     mod tests {
-        use super::part2_vec;
+        use super::{part2_vec, part2_worklist};
         use crate::{EXAMPLE_INPUT, FULL_INPUT};
 
         #[test]
@@ -172,13 +252,643 @@ pub mod part2_vec {
         fn test_part2_vec_full() {
             assert_eq!(part2_vec(FULL_INPUT), 8739);
         }
+
+        #[test]
+        fn test_part2_worklist_example() {
+            assert_eq!(part2_worklist(EXAMPLE_INPUT), 43);
+        }
+
+        #[test]
+        fn test_part2_worklist_full() {
+            assert_eq!(part2_worklist(FULL_INPUT), 8739);
+        }
+    }
+}
+
+//
+// The grid packed one bit per cell, with each row stored as a sequence of
+// `u64` words.  Neighbor counts are computed for 64 cells at a time using
+// bitwise adders, so both parts take time proportional to cells / 64.
+// Like `part2_vec`, this only implements the puzzle's rule.
+//
+pub mod bits {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct BitGrid {
+        // Bit `i` of word `w` in a row is column `64 * w + i`.  Bits beyond
+        // the last column are always zero.
+        words: Vec<u64>,
+        words_per_row: usize,
+        rows: usize,
+    }
+
+    impl BitGrid {
+        pub fn len(&self) -> usize {
+            self.words.iter().map(|word| word.count_ones() as usize).sum()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.words.iter().all(|&word| word == 0)
+        }
+
+        fn row(&self, row: usize) -> &[u64] {
+            &self.words[row * self.words_per_row .. (row + 1) * self.words_per_row]
+        }
+
+        //
+        // Compute the next generation (synchronously removing every roll
+        // with fewer than 4 neighbors) into `next`.  Returns the number of
+        // rolls removed.
+        //
+        fn next_generation(&self, next: &mut [u64]) -> usize {
+            let empty = vec![0; self.words_per_row];
+            let mut removed = 0;
+
+            for row in 0..self.rows {
+                let above = if row > 0 { self.row(row - 1) } else { &empty };
+                let current = self.row(row);
+                let below = if row + 1 < self.rows { self.row(row + 1) } else { &empty };
+
+                for word in 0..self.words_per_row {
+                    let crowded = at_least_four([
+                        left_neighbors(above, word), above[word], right_neighbors(above, word),
+                        left_neighbors(current, word), right_neighbors(current, word),
+                        left_neighbors(below, word), below[word], right_neighbors(below, word),
+                    ]);
+                    removed += (current[word] & !crowded).count_ones() as usize;
+                    next[row * self.words_per_row + word] = current[word] & crowded;
+                }
+            }
+
+            removed
+        }
+    }
+
+    //
+    // For each bit in `row[word]`, whether the cell to its left is occupied.
+    //
+    fn left_neighbors(row: &[u64], word: usize) -> u64 {
+        let carry = if word > 0 { row[word - 1] >> 63 } else { 0 };
+        (row[word] << 1) | carry
+    }
+
+    //
+    // For each bit in `row[word]`, whether the cell to its right is occupied.
+    //
+    fn right_neighbors(row: &[u64], word: usize) -> u64 {
+        let carry = if word + 1 < row.len() { row[word + 1] << 63 } else { 0 };
+        (row[word] >> 1) | carry
+    }
+
+    //
+    // For each of 64 cells, add up the 8 neighbor bits, and return which
+    // cells have at least 4 neighbors.  `ones` and `twos` are the low bits
+    // of a 2-bit counter per cell; `fours` is set (and stays set) when the
+    // counter overflows.
+    //
+    fn at_least_four(neighbors: [u64; 8]) -> u64 {
+        let mut ones = 0;
+        let mut twos = 0;
+        let mut fours = 0;
+        for neighbor in neighbors {
+            let carry = ones & neighbor;
+            ones ^= neighbor;
+            fours |= twos & carry;
+            twos ^= carry;
+        }
+        fours
+    }
+
+    pub fn parse_input(input: &str) -> BitGrid {
+        let rows = input.lines().count();
+        let columns = input.lines().map(|line| line.len()).max().unwrap_or(0);
+        let words_per_row = columns.div_ceil(64);
+        let mut words = vec![0; rows * words_per_row];
+
+        for (row, line) in input.lines().enumerate() {
+            for (col, ch) in line.chars().enumerate() {
+                if ch == '@' {
+                    words[row * words_per_row + col / 64] |= 1 << (col % 64);
+                }
+            }
+        }
+
+        BitGrid { words, words_per_row, rows }
+    }
+
+    pub fn part1_bits(input: &str) -> usize {
+        let grid = parse_input(input);
+        let mut next = vec![0; grid.words.len()];
+        grid.next_generation(&mut next)
+    }
+
+    pub fn part2_bits(input: &str) -> usize {
+        let mut grid = parse_input(input);
+        let mut next = vec![0; grid.words.len()];
+        let mut total = 0;
+
+        loop {
+            let removed = grid.next_generation(&mut next);
+            if removed == 0 {
+                break;
+            }
+            total += removed;
+            std::mem::swap(&mut grid.words, &mut next);
+        }
+
+        total
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{part1_bits, part2_bits};
+        use crate::{EXAMPLE_INPUT, FULL_INPUT, generated_grid, part1_set, part2_vec::part2_worklist};
+
+        #[test]
+        fn test_part1_bits_example() {
+            assert_eq!(part1_bits(EXAMPLE_INPUT), 13);
+        }
+
+        #[test]
+        fn test_part1_bits_full() {
+            assert_eq!(part1_bits(FULL_INPUT), 1419);
+        }
+
+        #[test]
+        fn test_part2_bits_example() {
+            assert_eq!(part2_bits(EXAMPLE_INPUT), 43);
+        }
+
+        #[test]
+        fn test_part2_bits_full() {
+            assert_eq!(part2_bits(FULL_INPUT), 8739);
+        }
+
+        #[test]
+        fn test_bits_word_boundaries() {
+            // A grid wider than two words, so that neighbors cross between words
+            let input = generated_grid(50, 150);
+
+            assert_eq!(part1_bits(&input), part1_set(&input));
+            assert_eq!(part2_bits(&input), part2_worklist(&input));
+        }
+    }
+}
+
+//
+// The roll removal process, one round ("wave") at a time, keeping track
+// of which rolls were removed in each wave.
+//
+pub mod automaton {
+    use std::fmt;
+
+    //
+    // How rolls are removed within a round.
+    //
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Update {
+        // Find all removable rolls based on the grid at the start of the
+        // round, then remove them together.  This is what `part2` does.
+        Synchronous,
+        // Scan the grid in row-major order, removing each roll as soon as
+        // it is found to be removable, so that rolls later in the scan see
+        // the removal.  This is what `part2_vec` does.
+        InPlace,
+    }
+
+    //
+    // Which cells count as neighbors of a cell.
+    //
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Neighborhood {
+        // The 4 orthogonally adjacent cells
+        VonNeumann,
+        // The 8 orthogonally or diagonally adjacent cells
+        Moore,
+        // All cells within a square of the given radius (radius 1 is Moore)
+        Radius(usize),
+        // The given (row, column) offsets from the cell
+        Custom(Vec<(isize, isize)>),
+    }
+
+    impl Neighborhood {
+        pub fn offsets(&self) -> Vec<(isize, isize)> {
+            match self {
+                Neighborhood::VonNeumann => vec![(-1, 0), (0, -1), (0, 1), (1, 0)],
+                Neighborhood::Moore => Neighborhood::Radius(1).offsets(),
+                Neighborhood::Radius(radius) => {
+                    let radius = *radius as isize;
+                    let mut offsets = Vec::new();
+                    for dr in -radius ..= radius {
+                        for dc in -radius ..= radius {
+                            if (dr, dc) != (0, 0) {
+                                offsets.push((dr, dc));
+                            }
+                        }
+                    }
+                    offsets
+                }
+                Neighborhood::Custom(offsets) => offsets.clone(),
+            }
+        }
+    }
+
+    //
+    // How a roll's neighbor count is compared against the threshold.
+    //
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Comparison {
+        Less,
+        LessOrEqual,
+        Equal,
+        GreaterOrEqual,
+        Greater,
+    }
+
+    //
+    // When a roll can be removed: if its number of neighboring rolls
+    // compares to `threshold` according to `comparison`.  With `wrap`,
+    // the grid is a torus, so neighbors off one edge come from the
+    // opposite edge; otherwise cells off the edge are empty.
+    //
+    // The default is the puzzle's rule: fewer than 4 of the 8 neighbors.
+    //
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Rule {
+        pub neighborhood: Neighborhood,
+        pub comparison: Comparison,
+        pub threshold: usize,
+        pub wrap: bool,
+    }
+
+    impl Default for Rule {
+        fn default() -> Self {
+            Rule {
+                neighborhood: Neighborhood::Moore,
+                comparison: Comparison::Less,
+                threshold: 4,
+                wrap: false,
+            }
+        }
+    }
+
+    impl Rule {
+        pub fn is_removable(&self, neighbors: usize) -> bool {
+            match self.comparison {
+                Comparison::Less => neighbors < self.threshold,
+                Comparison::LessOrEqual => neighbors <= self.threshold,
+                Comparison::Equal => neighbors == self.threshold,
+                Comparison::GreaterOrEqual => neighbors >= self.threshold,
+                Comparison::Greater => neighbors > self.threshold,
+            }
+        }
+
+        //
+        // Does removing a roll only ever make other rolls removable?
+        // If so, the final grid doesn't depend on the order of removal.
+        //
+        pub fn is_monotonic(&self) -> bool {
+            matches!(self.comparison, Comparison::Less | Comparison::LessOrEqual)
+        }
+    }
+
+    //
+    // A grid of cells, which may or may not contain a roll.
+    //
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Grid {
+        occupied: Vec<bool>,
+        rows: usize,
+        columns: usize,
+    }
+
+    impl Grid {
+        //
+        // Parse a grid where `@` is a roll.  Lines may have different
+        // lengths; the grid is as wide as the longest line.
+        //
+        pub fn parse(input: &str) -> Grid {
+            let rows = input.lines().count();
+            let columns = input.lines().map(|line| line.len()).max().unwrap_or(0);
+            let mut occupied = vec![false; rows * columns];
+            for (row, line) in input.lines().enumerate() {
+                for (col, ch) in line.chars().enumerate() {
+                    occupied[row * columns + col] = ch == '@';
+                }
+            }
+            Grid { occupied, rows, columns }
+        }
+
+        pub fn rows(&self) -> usize {
+            self.rows
+        }
+
+        pub fn columns(&self) -> usize {
+            self.columns
+        }
+
+        pub fn contains(&self, row: usize, col: usize) -> bool {
+            row < self.rows && col < self.columns && self.occupied[row * self.columns + col]
+        }
+
+        // The number of rolls in the grid
+        pub fn len(&self) -> usize {
+            self.occupied.iter().filter(|item| **item).count()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            !self.occupied.contains(&true)
+        }
+
+        // The (row, column) of every roll, in row-major order
+        pub fn rolls(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+            self.occupied.iter()
+                .enumerate()
+                .filter(|(_, occupied)| **occupied)
+                .map(|(index, _)| (index / self.columns, index % self.columns))
+        }
+
+        fn remove(&mut self, row: usize, col: usize) {
+            self.occupied[row * self.columns + col] = false;
+        }
+
+        //
+        // The (row, column) of the cell at `offset` from (row, col), or
+        // `None` if it is off the edge of a non-wrapping grid.
+        //
+        fn neighbor(&self, row: usize, col: usize, offset: (isize, isize), wrap: bool) -> Option<(usize, usize)> {
+            let r = row as isize + offset.0;
+            let c = col as isize + offset.1;
+            if wrap {
+                Some((r.rem_euclid(self.rows as isize) as usize, c.rem_euclid(self.columns as isize) as usize))
+            } else if r >= 0 && c >= 0 && (r as usize) < self.rows && (c as usize) < self.columns {
+                Some((r as usize, c as usize))
+            } else {
+                None
+            }
+        }
+
+        //
+        // The number of rolls at the given offsets from (row, col), not
+        // counting (row, col) itself.  On a torus smaller than the
+        // neighborhood, several offsets can land on the same cell (or back
+        // on (row, col)), so each cell is only counted once.
+        //
+        pub fn count_neighbors(&self, row: usize, col: usize, offsets: &[(isize, isize)], wrap: bool) -> usize {
+            let targets = offsets.iter()
+                .filter_map(|&offset| self.neighbor(row, col, offset, wrap))
+                .filter(|&target| target != (row, col));
+            if !wrap {
+                return targets.filter(|&(r, c)| self.contains(r, c)).count();
+            }
+
+            let mut targets: Vec<(usize, usize)> = targets.collect();
+            targets.sort_unstable();
+            targets.dedup();
+            targets.into_iter().filter(|&(r, c)| self.contains(r, c)).count()
+        }
+
+        //
+        // The rolls that could be removed right now (the answer to part 1,
+        // with the default rule).
+        //
+        pub fn removable(&self, rule: &Rule) -> Vec<(usize, usize)> {
+            let offsets = rule.neighborhood.offsets();
+            self.rolls()
+                .filter(|&(row, col)| rule.is_removable(self.count_neighbors(row, col, &offsets, rule.wrap)))
+                .collect()
+        }
+    }
+
+    impl fmt::Display for Grid {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if self.columns == 0 {
+                return Ok(());
+            }
+            for row in self.occupied.chunks(self.columns) {
+                let line: String = row.iter().map(|&occupied| if occupied { '@' } else { '.' }).collect();
+                writeln!(f, "{line}")?;
+            }
+            Ok(())
+        }
+    }
+
+    //
+    // The rolls removed in one round, and the number of rolls left
+    // afterwards.
+    //
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Wave {
+        pub removed: Vec<(usize, usize)>,
+        pub remaining: usize,
+    }
+
+    //
+    // The whole removal process: the starting grid, each wave of removals
+    // (the last of which removed at least one roll), and the stable grid
+    // where no more rolls can be removed.
+    //
+    // For a monotonic rule (like the default one), both kinds of `Update`
+    // end with the same stable grid, and so remove the same total number
+    // of rolls.  Removing a roll can only make its neighbors removable,
+    // never the reverse, so every roll that would be removed in one order
+    // is eventually removed in any other.  They can differ in the number
+    // of waves, and which rolls are in each wave.  For other rules, the
+    // results may differ.
+    //
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct History {
+        pub initial: Grid,
+        pub waves: Vec<Wave>,
+        pub stable: Grid,
+    }
+
+    impl History {
+        pub fn total_removed(&self) -> usize {
+            self.waves.iter().map(|wave| wave.removed.len()).sum()
+        }
+    }
+
+    //
+    // Remove rolls, in waves, until no more can be removed.
+    //
+    pub fn history(input: &str, update: Update) -> History {
+        history_with_rule(input, update, &Rule::default())
+    }
+
+    //
+    // Like `history`, but with a different rule for which rolls can be
+    // removed.  This always terminates, since every wave removes at least
+    // one roll.
+    //
+    pub fn history_with_rule(input: &str, update: Update, rule: &Rule) -> History {
+        let initial = Grid::parse(input);
+        let mut grid = initial.clone();
+        let mut remaining = grid.len();
+        let mut waves = Vec::new();
+        let offsets = rule.neighborhood.offsets();
+
+        loop {
+            let removed = match update {
+                Update::Synchronous => {
+                    let removed = grid.removable(rule);
+                    for &(row, col) in removed.iter() {
+                        grid.remove(row, col);
+                    }
+                    removed
+                }
+                Update::InPlace => {
+                    let mut removed = Vec::new();
+                    for row in 0..grid.rows {
+                        for col in 0..grid.columns {
+                            if grid.contains(row, col) &&
+                                rule.is_removable(grid.count_neighbors(row, col, &offsets, rule.wrap))
+                            {
+                                grid.remove(row, col);
+                                removed.push((row, col));
+                            }
+                        }
+                    }
+                    removed
+                }
+            };
+
+            if removed.is_empty() {
+                break;
+            }
+            remaining -= removed.len();
+            waves.push(Wave { removed, remaining });
+        }
+
+        History { initial, waves, stable: grid }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{history, history_with_rule, Update, Grid, Rule, Neighborhood, Comparison};
+        use crate::EXAMPLE_INPUT;
+
+        #[test]
+        fn test_history_synchronous_example() {
+            let history = history(EXAMPLE_INPUT, Update::Synchronous);
+            let sizes: Vec<usize> = history.waves.iter().map(|wave| wave.removed.len()).collect();
+            assert_eq!(sizes, [13, 12, 7, 5, 2, 1, 1, 1, 1]);
+            assert_eq!(history.total_removed(), 43);
+            assert_eq!(history.waves.last().unwrap().remaining, history.stable.len());
+            assert_eq!(history.waves[0].removed[..3], [(0, 2), (0, 3), (0, 5)]);
+            assert_eq!(history.stable.to_string(), "\
+..........
+..........
+..........
+....@@....
+...@@@@...
+...@@@@@..
+...@.@.@@.
+...@@.@@@.
+...@@@@@..
+....@@@...
+");
+        }
+
+        #[test]
+        fn test_history_same_final_count() {
+            let synchronous = history(EXAMPLE_INPUT, Update::Synchronous);
+            let in_place = history(EXAMPLE_INPUT, Update::InPlace);
+            assert_eq!(in_place.total_removed(), 43);
+            assert_eq!(in_place.stable, synchronous.stable);
+            assert!(in_place.waves.len() <= synchronous.waves.len());
+        }
+
+        #[test]
+        fn test_default_rule() {
+            let grid = Grid::parse(EXAMPLE_INPUT);
+            assert_eq!(grid.removable(&Rule::default()).len(), 13);
+            let rule = Rule { neighborhood: Neighborhood::Radius(1), ..Rule::default() };
+            assert_eq!(grid.removable(&rule).len(), 13);
+            let offsets = Neighborhood::Moore.offsets();
+            let rule = Rule { neighborhood: Neighborhood::Custom(offsets), ..Rule::default() };
+            assert_eq!(history_with_rule(EXAMPLE_INPUT, Update::Synchronous, &rule).total_removed(), 43);
+        }
+
+        #[test]
+        fn test_neighborhoods() {
+            assert_eq!(Neighborhood::VonNeumann.offsets().len(), 4);
+            assert_eq!(Neighborhood::Moore.offsets().len(), 8);
+            assert_eq!(Neighborhood::Radius(2).offsets().len(), 24);
+
+            // The center roll has 4 orthogonal neighbors, and 4 diagonal ones
+            let grid = Grid::parse("@@@\n@@@\n@@@\n");
+            let count = |neighborhood: Neighborhood, wrap| {
+                grid.count_neighbors(1, 1, &neighborhood.offsets(), wrap)
+            };
+            assert_eq!(count(Neighborhood::VonNeumann, false), 4);
+            assert_eq!(count(Neighborhood::Moore, false), 8);
+            assert_eq!(count(Neighborhood::Custom(vec![(-1, -1), (1, 1), (2, 2)]), false), 2);
+
+            // A corner roll has 3 neighbors, unless the grid wraps
+            assert_eq!(grid.count_neighbors(0, 0, &Neighborhood::Moore.offsets(), false), 3);
+            assert_eq!(grid.count_neighbors(0, 0, &Neighborhood::Moore.offsets(), true), 8);
+        }
+
+        #[test]
+        fn test_wrap() {
+            // On a torus, every roll in a full grid has 8 neighbors
+            let rule = Rule { wrap: true, ..Rule::default() };
+            let full = "@@@@@\n@@@@@\n@@@@@\n@@@@@\n";
+            assert_eq!(history_with_rule(full, Update::Synchronous, &rule).total_removed(), 0);
+            assert_eq!(history(full, Update::Synchronous).total_removed(), 4);
+        }
+
+        #[test]
+        fn test_wrap_small_grid() {
+            // The only other cell is counted once, and a cell is never its
+            // own neighbor
+            let grid = Grid::parse("@@\n");
+            assert_eq!(grid.count_neighbors(0, 0, &Neighborhood::Moore.offsets(), true), 1);
+            assert_eq!(Grid::parse("@\n").count_neighbors(0, 0, &Neighborhood::Moore.offsets(), true), 0);
+
+            // A radius 2 neighborhood covers all of a 3x3 torus
+            let grid = Grid::parse("@@@\n@@@\n@@@\n");
+            assert_eq!(grid.count_neighbors(0, 0, &Neighborhood::Radius(2).offsets(), true), 8);
+        }
+
+        #[test]
+        fn test_comparison() {
+            // Remove crowded rolls (more than 4 neighbors), all at once
+            let rule = Rule { comparison: Comparison::Greater, ..Rule::default() };
+            let history = history_with_rule("@@@\n@@@\n@@@\n", Update::Synchronous, &rule);
+            assert!(!rule.is_monotonic());
+            assert_eq!(history.waves.len(), 1);
+            assert_eq!(history.waves[0].removed, [(0, 1), (1, 0), (1, 1), (1, 2), (2, 1)]);
+            assert_eq!(history.stable.to_string(), "@.@\n...\n@.@\n");
+        }
+    }
+}
+
+//
+// A grid with (pseudo-random) rolls in about 70% of the cells, which is
+// similar to the density of the real input.  The generator is a simple
+// LCG, so the same size always gives the same grid.  Used by the tests
+// and benchmarks.
+//
+pub fn generated_grid(rows: usize, columns: usize) -> String {
+    let mut state: u32 = 12345;
+    let mut grid = String::with_capacity(rows * (columns + 1));
+    for _row in 0..rows {
+        for _col in 0..columns {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            grid.push(if (state >> 16) % 10 < 7 { '@' } else { '.' });
+        }
+        grid.push('\n');
     }
+    grid
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, EXAMPLE_INPUT, FULL_INPUT};
-    
+    use super::{part1, part2, part1_set, part2_set, EXAMPLE_INPUT, FULL_INPUT};
+    use super::{generated_grid, part2_vec, bits};
+    use super::automaton::{history, Grid, Rule, Update};
+
     #[test]
     fn test_part1_example() {
         assert_eq!(part1(EXAMPLE_INPUT), 13);
@@ -198,6 +908,25 @@ mod tests {
     fn test_part2_full() {
         assert_eq!(part2(FULL_INPUT), 8739);
     }
+
+    #[test]
+    fn test_fast_paths_match_default_rule() {
+        let edges = "@@@@@\n@@@@@\n@@.@@\n@@@@@\n@@@@@\n";
+        let generated = generated_grid(40, 70);
+        for input in [EXAMPLE_INPUT, edges, &generated] {
+            let removable = Grid::parse(input).removable(&Rule::default()).len();
+            assert_eq!(part1(input), removable);
+            assert_eq!(part1_set(input), removable);
+            assert_eq!(bits::part1_bits(input), removable);
+
+            let removed = history(input, Update::Synchronous).total_removed();
+            assert_eq!(part2(input), removed);
+            assert_eq!(part2_set(input), removed);
+            assert_eq!(part2_vec::part2_vec(input), removed);
+            assert_eq!(part2_vec::part2_worklist(input), removed);
+            assert_eq!(bits::part2_bits(input), removed);
+        }
+    }
 }
 
 pub static EXAMPLE_INPUT: &str = "\
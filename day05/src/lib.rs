@@ -1,103 +1,466 @@
 use std::ops::RangeInclusive;
-use nom::{IResult, Parser, bytes::complete::tag, character::complete::{newline, u64}, combinator::all_consuming, multi::many1, sequence::{separated_pair, terminated}};
-use itertools::Itertools;
+use nom::{IResult, Parser, bytes::complete::tag, character::complete::{newline, u64}, combinator::{all_consuming, opt}, multi::many0, sequence::{preceded, separated_pair, terminated}};
+
+pub use range_set::RangeSet;
+pub use query::FreshIndex;
 
 pub fn part1(input: &str) -> usize {
-    let (mut ranges, mut ids) = parse_input(input);
-    ranges.sort_unstable_by_key(|range| *range.start());
-    ranges = ranges.into_iter()
-        .coalesce(|first, second| {
-            if second.start() <= first.end() {
-                Ok(*first.start() ..= *first.end().max(second.end()))
+    let (ranges, ids) = parse_input(input);
+    let fresh = RangeSet::from_ranges(ranges);
+    ids.into_iter().filter(|&id| fresh.contains(id)).count()
+}
+
+pub fn part2(input: &str) -> usize {
+    let (ranges, _ids) = parse_input(input);
+    let size = RangeSet::from_ranges(ranges).checked_size().expect("Too many fresh IDs");
+    usize::try_from(size).expect("Too many fresh IDs")
+}
+
+pub fn both(input: &str) -> (usize, u64) {
+    let (ranges, ids) = parse_input(input);
+    let fresh = RangeSet::from_ranges(ranges);
+    let result1 = ids.into_iter().filter(|&id| fresh.contains(id)).count();
+    let result2 = fresh.checked_size().expect("Too many fresh IDs");
+    (result1, result2)
+}
+
+pub mod range_set {
+    use std::ops::RangeInclusive;
+    use itertools::Itertools;
+
+    //
+    // A set of `u64`s, stored as a sorted list of disjoint ranges.
+    // Overlapping ranges are merged as they are added.  Optionally,
+    // adjacent ranges (like 3-5 and 6-8) are merged, too.
+    //
+    #[derive(Debug, Clone, Default)]
+    pub struct RangeSet {
+        ranges: Vec<RangeInclusive<u64>>,
+        merge_adjacent: bool,
+    }
+
+    //
+    // Sets are equal if they have the same ranges.  Whether they merge
+    // adjacent ranges only affects what happens to them later.
+    //
+    impl PartialEq for RangeSet {
+        fn eq(&self, other: &RangeSet) -> bool {
+            self.ranges == other.ranges
+        }
+    }
+
+    impl Eq for RangeSet {}
+
+    impl RangeSet {
+        pub fn new() -> Self {
+            RangeSet::default()
+        }
+
+        //
+        // An empty set which merges adjacent ranges, not just overlapping
+        // ones.  Sets produced from this one (by union, etc.) do the same.
+        //
+        pub fn merging_adjacent() -> Self {
+            RangeSet { ranges: Vec::new(), merge_adjacent: true }
+        }
+
+        //
+        // Build a set from ranges in any order, which may overlap.
+        // This is faster than inserting the ranges one at a time.
+        //
+        pub fn from_ranges(ranges: impl IntoIterator<Item = RangeInclusive<u64>>) -> Self {
+            RangeSet::new().with_ranges(ranges)
+        }
+
+        //
+        // Like `from_ranges`, but merging adjacent ranges.
+        //
+        pub fn from_ranges_merging_adjacent(ranges: impl IntoIterator<Item = RangeInclusive<u64>>) -> Self {
+            RangeSet::merging_adjacent().with_ranges(ranges)
+        }
+
+        // Add `ranges` (in any order) to the set.
+        fn with_ranges(mut self, ranges: impl IntoIterator<Item = RangeInclusive<u64>>) -> Self {
+            let mut ranges: Vec<_> = std::mem::take(&mut self.ranges).into_iter()
+                .chain(ranges.into_iter().filter(|range| !range.is_empty()))
+                .collect();
+            ranges.sort_unstable_by_key(|range| *range.start());
+            let ranges = ranges.into_iter()
+                .coalesce(|first, second| {
+                    if !self.separate(*first.end(), *second.start()) {
+                        Ok(*first.start() ..= *first.end().max(second.end()))
+                    } else {
+                        Err((first, second))
+                    }
+                })
+                .collect();
+            self.with_result(ranges)
+        }
+
+        //
+        // Would a range ending at `end` stay separate from a range
+        // starting at `start`?
+        //
+        fn separate(&self, end: u64, start: u64) -> bool {
+            if self.merge_adjacent {
+                end.checked_add(1).is_some_and(|next| next < start)
             } else {
-                Err((first, second))
+                end < start
             }
-        })
-        .collect();
-    let mut ranges = ranges.into_iter();
-
-    ids.sort_unstable();
-
-    let mut current_range = ranges.next().unwrap();
-    let mut result = 0;
-    for id in ids {
-        while id > *current_range.end() {
-            if let Some(next_range) = ranges.next() {
-                current_range = next_range;
+        }
+
+        // A set with the same options as this one, from ranges which are
+        // already sorted and disjoint
+        fn with_result(&self, ranges: Vec<RangeInclusive<u64>>) -> RangeSet {
+            RangeSet { ranges, merge_adjacent: self.merge_adjacent }
+        }
+
+        //
+        // Add a range to the set, merging it with any ranges it overlaps.
+        //
+        pub fn insert(&mut self, range: RangeInclusive<u64>) {
+            if range.is_empty() {
+                return;
+            }
+
+            // The ranges in first..last overlap (or touch) the new one
+            let first = self.ranges.partition_point(|r| self.separate(*r.end(), *range.start()));
+            let last = self.ranges.partition_point(|r| !self.separate(*range.end(), *r.start()));
+            if first == last {
+                self.ranges.insert(first, range);
             } else {
-                break;
+                let start = *range.start().min(self.ranges[first].start());
+                let end = *range.end().max(self.ranges[last - 1].end());
+                self.ranges.splice(first..last, [start ..= end]);
             }
         }
 
-        if current_range.contains(&id) {
-            result += 1;
+        pub fn contains(&self, value: u64) -> bool {
+            let index = self.ranges.partition_point(|range| *range.end() < value);
+            self.ranges.get(index).is_some_and(|range| range.contains(&value))
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.ranges.is_empty()
+        }
+
+        //
+        // The number of values in the set.  This is computed from the
+        // ends of the ranges, so it is fast even for huge ranges.  The
+        // full range of `u64` has 2^64 values, so this returns a `u128`.
+        //
+        pub fn size(&self) -> u128 {
+            self.ranges.iter().map(|range| (range.end() - range.start()) as u128 + 1).sum()
+        }
+
+        //
+        // The number of values in the set, or `None` if it doesn't fit
+        // in a `u64`.
+        //
+        pub fn checked_size(&self) -> Option<u64> {
+            u64::try_from(self.size()).ok()
+        }
+
+        //
+        // The disjoint ranges in the set, in increasing order.
+        //
+        pub fn iter(&self) -> std::slice::Iter<'_, RangeInclusive<u64>> {
+            self.ranges.iter()
+        }
+
+        pub fn union(&self, other: &RangeSet) -> RangeSet {
+            self.clone().with_ranges(other.ranges.iter().cloned())
+        }
+
+        pub fn intersection(&self, other: &RangeSet) -> RangeSet {
+            let mut ranges = Vec::new();
+            let (mut i, mut j) = (0, 0);
+            while i < self.ranges.len() && j < other.ranges.len() {
+                let (a, b) = (&self.ranges[i], &other.ranges[j]);
+                let start = *a.start().max(b.start());
+                let end = *a.end().min(b.end());
+                if start <= end {
+                    ranges.push(start ..= end);
+                }
+                // Move past whichever range ends first
+                if a.end() < b.end() {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+            }
+            // Pieces of adjacent ranges in `other` may need merging
+            self.with_result(Vec::new()).with_ranges(ranges)
+        }
+
+        //
+        // The values in `self` that are not in `other`.
+        //
+        pub fn difference(&self, other: &RangeSet) -> RangeSet {
+            let mut ranges = Vec::new();
+            let mut j = 0;
+            for range in self.ranges.iter() {
+                let mut start = *range.start();
+                let end = *range.end();
+                let mut covered = false;
+
+                // Cut out the parts of `range` covered by ranges in `other`.
+                // A range in `other` that extends past `end` may also
+                // overlap the next range in `self`, so don't move past it.
+                while let Some(cut) = other.ranges.get(j) && *cut.start() <= end {
+                    if *cut.end() >= start {
+                        if *cut.start() > start {
+                            ranges.push(start ..= cut.start() - 1);
+                        }
+                        if *cut.end() >= end {
+                            covered = true;
+                            break;
+                        }
+                        start = cut.end() + 1;
+                    }
+                    j += 1;
+                }
+
+                if !covered {
+                    ranges.push(start ..= end);
+                }
+            }
+            self.with_result(ranges)
+        }
+
+        //
+        // The values within `bounds` that are not in the set.
+        //
+        pub fn complement(&self, bounds: RangeInclusive<u64>) -> RangeSet {
+            self.with_result(Vec::new()).with_ranges([bounds]).difference(self)
         }
     }
 
-    result
-}
+    impl<'a> IntoIterator for &'a RangeSet {
+        type Item = &'a RangeInclusive<u64>;
+        type IntoIter = std::slice::Iter<'a, RangeInclusive<u64>>;
 
-pub fn part2(input: &str) -> usize {
-    let (mut ranges, _ids) = parse_input(input);
-    ranges.sort_by_key(|range| *range.start());
-
-    // Combine overlapping ranges
-    ranges.into_iter().coalesce(|first, second| {
-        if second.start() <= first.end() {
-            Ok(*first.start() ..= *first.end().max(second.end()))
-        } else {
-            Err((first, second))
-        }
-    })
-    .map(|range| range.count())
-    .sum()
-}
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter()
+        }
+    }
 
-pub fn both(input: &str) -> (usize, u64) {
-    let (mut ranges, mut ids) = parse_input(input);
-    ranges.sort_unstable_by_key(|range| *range.start());
-    ranges = ranges.into_iter()
-        .coalesce(|first, second| {
-            if second.start() <= first.end() {
-                Ok(*first.start() ..= *first.end().max(second.end()))
-            } else {
-                Err((first, second))
+    impl FromIterator<RangeInclusive<u64>> for RangeSet {
+        fn from_iter<I: IntoIterator<Item = RangeInclusive<u64>>>(iter: I) -> Self {
+            RangeSet::from_ranges(iter)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::ops::RangeInclusive;
+        use super::RangeSet;
+
+        fn ranges(set: &RangeSet) -> Vec<(u64, u64)> {
+            set.iter().map(|range| (*range.start(), *range.end())).collect()
+        }
+
+        #[test]
+        fn test_insert() {
+            let mut set = RangeSet::new();
+            // An empty range is ignored
+            let empty = RangeInclusive::new(7, 6);
+            for range in [10..=14, 3..=5, 16..=20, 12..=18, 30..=30, empty] {
+                set.insert(range);
             }
-        })
-        .collect();
+            assert_eq!(ranges(&set), [(3, 5), (10, 20), (30, 30)]);
+            assert_eq!(set, RangeSet::from_ranges([3..=5, 10..=14, 16..=20, 12..=18, 30..=30]));
+            assert_eq!(set.size(), 3 + 11 + 1);
 
-    let result2 = ranges.iter().map(|range| range.end() - range.start() + 1).sum();
+            set.insert(0..=40);
+            assert_eq!(ranges(&set), [(0, 40)]);
+        }
 
-    let mut ranges = ranges.into_iter();
+        #[test]
+        fn test_contains() {
+            let set: RangeSet = [3..=5, 10..=14, 16..=20, 12..=18].into_iter().collect();
+            let fresh: Vec<u64> = (0..25).filter(|&id| set.contains(id)).collect();
+            assert_eq!(fresh, [3, 4, 5, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20]);
+            assert!(!RangeSet::new().contains(0));
+        }
 
-    ids.sort_unstable();
+        #[test]
+        fn test_set_operations() {
+            let a = RangeSet::from_ranges([0..=10, 20..=30, 40..=50]);
+            let b = RangeSet::from_ranges([5..=25, 28..=28, 45..=60]);
+            assert_eq!(ranges(&a.union(&b)), [(0, 30), (40, 60)]);
+            assert_eq!(ranges(&a.intersection(&b)), [(5, 10), (20, 25), (28, 28), (45, 50)]);
+            assert_eq!(ranges(&a.difference(&b)), [(0, 4), (26, 27), (29, 30), (40, 44)]);
+            assert_eq!(ranges(&b.difference(&a)), [(11, 19), (51, 60)]);
+            assert_eq!(ranges(&a.complement(0..=100)), [(11, 19), (31, 39), (51, 100)]);
+            assert_eq!(ranges(&a.complement(5..=45)), [(11, 19), (31, 39)]);
+            assert!(a.difference(&a).is_empty());
+            assert!(RangeSet::new().complement(0..=u64::MAX).contains(u64::MAX));
+        }
 
-    let mut current_range = ranges.next().unwrap();
-    let mut result1 = 0;
-    for id in ids {
-        while id > *current_range.end() {
-            if let Some(next_range) = ranges.next() {
-                current_range = next_range;
-            } else {
-                break;
+        #[test]
+        fn test_adjacent() {
+            let input = [3..=5, 6..=8, 10..=12, 13..=13, 20..=25];
+            assert_eq!(ranges(&RangeSet::from_ranges(input.clone())), [(3, 5), (6, 8), (10, 12), (13, 13), (20, 25)]);
+            let set = RangeSet::from_ranges_merging_adjacent(input.clone());
+            assert_eq!(ranges(&set), [(3, 8), (10, 13), (20, 25)]);
+            assert_eq!(set.size(), RangeSet::from_ranges(input).size());
+
+            let mut set = RangeSet::merging_adjacent();
+            for range in [10..=12, 14..=15, 13..=13, 16..=16, 8..=8, 0..=u64::MAX - 1] {
+                set.insert(range);
+                let last = set.iter().last().unwrap();
+                assert!(set.iter().all(|range| range.end() < last.start() || range == last));
             }
+            assert_eq!(ranges(&set), [(0, u64::MAX - 1)]);
+            set.insert(u64::MAX ..= u64::MAX);
+            assert_eq!(ranges(&set), [(0, u64::MAX)]);
+
+            let holes = RangeSet::merging_adjacent().union(&RangeSet::from_ranges([0..=3, 4..=5]));
+            assert_eq!(ranges(&holes), [(0, 5)]);
+
+            // Intersecting with adjacent ranges still merges them
+            let mut set = RangeSet::from_ranges_merging_adjacent([0..=20]);
+            set = set.intersection(&RangeSet::from_ranges([0..=3, 4..=10]));
+            assert_eq!(ranges(&set), [(0, 10)]);
+            set.insert(11..=11);
+            assert_eq!(ranges(&set), [(0, 11)]);
+
+            // Equality only depends on the ranges
+            assert_eq!(RangeSet::from_ranges_merging_adjacent([1..=5]), RangeSet::from_ranges([1..=5]));
+            assert_ne!(RangeSet::from_ranges_merging_adjacent([1..=2, 3..=5]), RangeSet::from_ranges([1..=2, 3..=5]));
         }
 
-        if current_range.contains(&id) {
-            result1 += 1;
+        #[test]
+        fn test_huge_ranges() {
+            let set = RangeSet::from_ranges([0..=u64::MAX]);
+            assert_eq!(set.size(), 1 << 64);
+            assert_eq!(set.checked_size(), None);
+
+            let set = RangeSet::from_ranges([1..=u64::MAX, 100..=200]);
+            assert_eq!(set.checked_size(), Some(u64::MAX));
+            assert_eq!(set.complement(0..=u64::MAX).checked_size(), Some(1));
         }
     }
+}
 
-    (result1, result2)
+//
+// Answers questions about individual IDs, as they arrive, without needing
+// the whole list of IDs up front.
+//
+pub mod query {
+    use std::ops::RangeInclusive;
+    use super::RangeSet;
+
+    #[derive(Debug, Clone)]
+    pub struct FreshIndex {
+        // The merged ranges, for fast membership tests
+        fresh: RangeSet,
+        // The original ranges (with their index in the input), sorted
+        // by start
+        by_start: Vec<(usize, RangeInclusive<u64>)>,
+        // max_end[i] is the largest end of by_start[..=i]
+        max_end: Vec<u64>,
+    }
+
+    impl FreshIndex {
+        pub fn new(ranges: &[RangeInclusive<u64>]) -> Self {
+            let fresh = RangeSet::from_ranges(ranges.iter().cloned());
+
+            let mut by_start: Vec<_> = ranges.iter().cloned().enumerate().collect();
+            by_start.sort_by_key(|(_, range)| *range.start());
+            let max_end = by_start.iter()
+                .scan(0, |max_end, (_, range)| {
+                    *max_end = (*max_end).max(*range.end());
+                    Some(*max_end)
+                })
+                .collect();
+
+            FreshIndex { fresh, by_start, max_end }
+        }
+
+        pub fn fresh(&self) -> &RangeSet {
+            &self.fresh
+        }
+
+        //
+        // Is `id` fresh?  This is a binary search of the merged ranges,
+        // so O(log n).
+        //
+        pub fn contains(&self, id: u64) -> bool {
+            self.fresh.contains(id)
+        }
+
+        //
+        // The indices (within the input) of the ranges containing `id`,
+        // in increasing order.
+        //
+        pub fn covering(&self, id: u64) -> Vec<usize> {
+            // Only ranges starting at or before `id` can contain it.  Walk
+            // backwards from there until no earlier range reaches `id`.
+            let end = self.by_start.partition_point(|(_, range)| *range.start() <= id);
+            let mut result: Vec<usize> = (0..end).rev()
+                .take_while(|&i| self.max_end[i] >= id)
+                .filter(|&i| *self.by_start[i].1.end() >= id)
+                .map(|i| self.by_start[i].0)
+                .collect();
+            result.sort_unstable();
+            result
+        }
+
+        //
+        // Whether each of `ids` is fresh, in the same order as `ids`.
+        //
+        pub fn contains_batch(&self, ids: &[u64]) -> Vec<bool> {
+            ids.iter().map(|&id| self.contains(id)).collect()
+        }
+
+        //
+        // The number of fresh IDs (the answer to part 1).
+        //
+        pub fn count_fresh(&self, ids: impl IntoIterator<Item = u64>) -> usize {
+            ids.into_iter().filter(|&id| self.contains(id)).count()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::FreshIndex;
+
+        #[test]
+        fn test_contains() {
+            let index = FreshIndex::new(&[3..=5, 10..=14, 16..=20, 12..=18]);
+            assert_eq!(index.contains_batch(&[32, 17, 1, 5, 11, 8]), [false, true, false, true, true, false]);
+            assert_eq!(index.count_fresh([1, 5, 8, 11, 17, 32]), 3);
+        }
+
+        #[test]
+        fn test_covering() {
+            let index = FreshIndex::new(&[3..=5, 10..=14, 16..=20, 12..=18, 0..=100]);
+            assert_eq!(index.covering(1), [4]);
+            assert_eq!(index.covering(5), [0, 4]);
+            assert_eq!(index.covering(13), [1, 3, 4]);
+            assert_eq!(index.covering(17), [2, 3, 4]);
+            assert_eq!(index.covering(101), [] as [usize; 0]);
+
+            let index = FreshIndex::new(&[3..=5, 10..=14, 16..=20, 12..=18]);
+            assert_eq!(index.covering(15), [3]);
+            assert_eq!(index.covering(8), [] as [usize; 0]);
+        }
+    }
 }
 
+//
+// Parse the ranges, then a blank line, then the IDs.  Either list may be
+// empty, and if there are no IDs, the blank line is optional.
+//
 pub fn parse_input(input: &str) -> (Vec<RangeInclusive<u64>>, Vec<u64>) {
     let (_, (ranges, ids)) = all_consuming(
-        separated_pair(parse_ranges, newline, parse_ids)
+        (parse_ranges, opt(preceded(newline, parse_ids)))
     ).parse(input)
     .expect("Invalid input");
 
-    (ranges, ids)
+    (ranges, ids.unwrap_or_default())
 }
 
 fn parse_range(input: &str) -> IResult<&str, RangeInclusive<u64>> {
@@ -107,16 +470,16 @@ fn parse_range(input: &str) -> IResult<&str, RangeInclusive<u64>> {
 }
 
 fn parse_ranges(input: &str) -> IResult<&str, Vec<RangeInclusive<u64>>> {
-    many1(parse_range).parse(input)
+    many0(parse_range).parse(input)
 }
 
 fn parse_ids(input: &str) -> IResult<&str, Vec<u64>> {
-    many1(terminated(u64, newline)).parse(input)
+    many0(terminated(u64, newline)).parse(input)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, both};
+    use super::{part1, part2, both, parse_input};
     
     static EXAMPLE_INPUT: &str = "\
 3-5
@@ -162,4 +525,22 @@ mod tests {
     fn test_both_full() {
         assert_eq!(both(FULL_INPUT), (874, 348548952146313));
     }
+
+    #[test]
+    fn test_empty_sections() {
+        assert_eq!(parse_input("3-5\n10-14\n\n"), (vec![3..=5, 10..=14], vec![]));
+        assert_eq!(parse_input("3-5\n10-14\n"), (vec![3..=5, 10..=14], vec![]));
+        assert_eq!(parse_input("\n1\n5\n"), (vec![], vec![1, 5]));
+        assert_eq!(parse_input(""), (vec![], vec![]));
+
+        assert_eq!(both("3-5\n10-14\n\n"), (0, 8));
+        assert_eq!(both("\n1\n5\n"), (0, 0));
+        assert_eq!(part1("\n1\n5\n"), 0);
+        assert_eq!(part2(""), 0);
+    }
+
+    #[test]
+    fn test_huge_range() {
+        assert_eq!(both("1-18446744073709551615\n\n0\n18446744073709551615\n"), (1, u64::MAX));
+    }
 }
@@ -1,106 +1,462 @@
-use nom::{IResult, Parser, branch::alt, character::complete::{char, multispace0, multispace1, newline, space0, space1, u64}, combinator::{all_consuming, opt}, multi::{many1, separated_list1}, sequence::delimited};
+use std::error::Error;
+use std::fmt;
+use itertools::Itertools;
 
-pub fn part1(input: &str) -> u64 {
-    let (numbers, operators) = parse_input(input);
+pub fn part1(input: &str) -> i128 {
+    parse_input(input).total(Reading::Rows).expect("Invalid worksheet")
+}
+
+//
+// In this part, numbers are top-to-bottom in a single column, and the
+// columns are read right-to-left.  See `Problem::column_numbers`.
+//
+pub fn part2(input: &str) -> i128 {
+    parse_input(input).total(Reading::Columns).expect("Invalid worksheet")
+}
+
+pub fn parse_input(input: &str) -> Worksheet {
+    Worksheet::parse(input).expect("Invalid input")
+}
+
+//
+// A worksheet is a sequence of problems, side by side.  Each problem is
+// a block of columns, separated from the next problem by a column of
+// spaces.  The last line holds one operator per problem, which may be
+// anywhere within the problem's columns.  The lines above it hold the
+// numbers.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worksheet {
+    problems: Vec<Problem>,
+}
+
+//
+// One problem: the raw text of its number lines (all padded with spaces
+// to the same width), and its operator.  `column` is the (zero-based)
+// column of the problem's first character within the worksheet, and
+// `operator_column` is the column of the operator.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub column: usize,
+    pub lines: Vec<Vec<u8>>,
+    pub operator: Operator,
+    pub operator_column: usize,
+}
+
+//
+// How the numbers in a problem are read: a row at a time (part 1), or
+// a column at a time (part 2).
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reading {
+    Rows,
+    Columns,
+}
 
-    operators.into_iter().enumerate().map(|(index, ch)| {
-        let values = numbers.iter().map(|row| row[index]);
-        match ch {
-            '+' => values.sum::<u64>(),
-            '*' => values.product(),
-            _ => unreachable!()
+//
+// The operators, and their symbols in a worksheet.  An operator is applied
+// to the numbers from left to right, in the order they are read; so
+// `-` subtracts the second and later numbers from the first, and `^`
+// raises the first number to the power of the second, then that result
+// to the power of the third, and so on.
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,        // +
+    Subtract,   // -
+    Multiply,   // *
+    Divide,     // /
+    Min,        // <
+    Max,        // >
+    Power,      // ^
+}
+
+impl Operator {
+    pub fn from_symbol(symbol: char) -> Option<Operator> {
+        match symbol {
+            '+' => Some(Operator::Add),
+            '-' => Some(Operator::Subtract),
+            '*' => Some(Operator::Multiply),
+            '/' => Some(Operator::Divide),
+            '<' => Some(Operator::Min),
+            '>' => Some(Operator::Max),
+            '^' => Some(Operator::Power),
+            _ => None
+        }
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Subtract => '-',
+            Operator::Multiply => '*',
+            Operator::Divide => '/',
+            Operator::Min => '<',
+            Operator::Max => '>',
+            Operator::Power => '^',
         }
-    }).sum()
+    }
+
+    //
+    // Combine the result so far with the next number.  Returns `None`
+    // on overflow or division by zero.  Division must be exact; if it
+    // isn't, `Some(Err(remainder))` is returned.
+    //
+    fn apply(self, acc: i128, value: i128) -> Option<Result<i128, i128>> {
+        let result = match self {
+            Operator::Add => acc.checked_add(value)?,
+            Operator::Subtract => acc.checked_sub(value)?,
+            Operator::Multiply => acc.checked_mul(value)?,
+            Operator::Divide => {
+                let remainder = acc.checked_rem(value)?;
+                if remainder != 0 {
+                    return Some(Err(remainder));
+                }
+                acc / value
+            }
+            Operator::Min => acc.min(value),
+            Operator::Max => acc.max(value),
+            Operator::Power => acc.checked_pow(u32::try_from(value).ok()?)?,
+        };
+        Some(Ok(result))
+    }
+}
+
+//
+// Problems evaluating a problem.  `column` is the column where the
+// problem starts.  As in `WorksheetError`, lines and columns are
+// one-based.
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    NoNumbers { column: usize },
+    // A row (or column) has a gap between its digits.  `line` and
+    // `gap_column` locate the first space in the gap.
+    SplitNumber { column: usize, line: usize, gap_column: usize },
+    Overflow { column: usize },
+    DivisionByZero { column: usize },
+    InexactDivision { column: usize, dividend: i128, divisor: i128 },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::NoNumbers { column } =>
+                write!(f, "problem at column {column} has no numbers"),
+            EvalError::SplitNumber { column, line, gap_column } =>
+                write!(f, "problem at column {column}: space between digits at line {line}, column {gap_column}"),
+            EvalError::Overflow { column } =>
+                write!(f, "problem at column {column} overflows"),
+            EvalError::DivisionByZero { column } =>
+                write!(f, "problem at column {column} divides by zero"),
+            EvalError::InexactDivision { column, dividend, divisor } =>
+                write!(f, "problem at column {column}: {dividend} is not divisible by {divisor}"),
+        }
+    }
 }
 
+impl Error for EvalError {}
+
 //
-// In this part, numbers are top-to-bottom in a single column.
-// The operator appears on the last line, in the first column of a problem.
-// The operators line has trailing blank spaces to match the last column of digits.
-// Numbers in a given column may be top-aligned or bottom-aligned, so just ignore
-// those spaces.  We know we've finished a problem when there are no digits in
-// a column.
+// Problems with the layout of a worksheet.  Lines and columns are
+// one-based, like an editor's, and columns are counted after expanding
+// tabs.
 //
-pub fn part2(input: &str) -> u64 {
-    let mut lines: Vec<Vec<u8>> = input
-        .lines()
-        .map(|line| line.bytes().collect())
-        .collect();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorksheetError {
+    Empty,
+    InvalidCharacter { line: usize, column: usize, found: char },
+    MissingOperator { column: usize },
+    ExtraOperator { column: usize },
+    UnknownOperator { column: usize, symbol: char },
+}
 
-    // Add a blank column on the end since that triggers us to do the
-    // addition or subtraction.  (It's a terminator for the values.)
-    for line in lines.iter_mut() {
-        line.push(b' ');
+impl fmt::Display for WorksheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorksheetError::Empty =>
+                write!(f, "worksheet is empty"),
+            WorksheetError::InvalidCharacter { line, column, found } =>
+                write!(f, "line {line}, column {column}: expected a digit or space, found {found:?}"),
+            WorksheetError::MissingOperator { column } =>
+                write!(f, "problem at column {column} has no operator"),
+            WorksheetError::ExtraOperator { column } =>
+                write!(f, "extra operator at column {column}"),
+            WorksheetError::UnknownOperator { column, symbol } =>
+                write!(f, "unknown operator {symbol:?} at column {column}"),
+        }
     }
+}
+
+impl Error for WorksheetError {}
 
-    let operators = lines.pop().unwrap();
+// Tab stops are every 8 columns, unless specified otherwise
+pub const DEFAULT_TAB_WIDTH: usize = 8;
 
-    let mut operator = b'+';    // Will be overridden by first column
-    let mut result = 0;         // Overall function result
-    let mut values: Vec<u64> = Vec::new();
+impl Worksheet {
+    pub fn parse(input: &str) -> Result<Worksheet, WorksheetError> {
+        Worksheet::parse_with_tab_width(input, DEFAULT_TAB_WIDTH)
+    }
 
-    for (col, op) in operators.into_iter().enumerate() {
-        if op != b' ' {
-            // Remember the operator for this problem
-            operator = op;
+    //
+    // Parse a worksheet, expanding tabs to the next multiple of `tab_width`
+    // columns.  Lines may be different lengths (for example, if an editor
+    // stripped trailing spaces); missing cells are treated as spaces.
+    // Blank lines after the operator line are ignored.
+    //
+    pub fn parse_with_tab_width(input: &str, tab_width: usize) -> Result<Worksheet, WorksheetError> {
+        let mut lines: Vec<Vec<char>> = input.lines().map(|line| expand_tabs(line, tab_width)).collect();
+        while lines.last().is_some_and(|line| line.iter().all(|&ch| ch == ' ')) {
+            lines.pop();
         }
+        let operators = lines.pop().ok_or(WorksheetError::Empty)?;
 
-        // Gather digits from this column
-        let digits = lines.iter().filter_map(|line| {
-            let ch = line[col];
-            if ch == b' ' {
-                None
-            } else {
-                Some((ch - b'0') as u64)
+        for (line_num, line) in lines.iter().enumerate() {
+            if let Some(column) = line.iter().position(|&ch| ch != ' ' && !ch.is_ascii_digit()) {
+                return Err(WorksheetError::InvalidCharacter { line: line_num + 1, column: column + 1, found: line[column] });
             }
-        });
+        }
 
-        let value = digits.into_iter().reduce(|acc, digit| acc * 10 + digit);
-        
-        if let Some(value) = value {
-            // Push this value
-            values.push(value);
-        } else {
-            // Do the math on the gathered values
-            if operator == b'+' {
-                result += values.iter().sum::<u64>();
-            } else {
-                result += values.iter().product::<u64>();
+        let width = lines.iter().chain([&operators]).map(|line| line.len()).max().unwrap_or(0);
+
+        // Treat anything past the end of a line as a space.
+        let cell = |line: &[char], col: usize| line.get(col).copied().unwrap_or(' ');
+        let is_blank_column = |col: usize| {
+            lines.iter().chain([&operators]).all(|line| cell(line, col) == ' ')
+        };
+
+        let mut problems = Vec::new();
+        let mut col = 0;
+        while col < width {
+            if is_blank_column(col) {
+                col += 1;
+                continue;
             }
-            values.clear();
+
+            // Find the extent of this problem's block of columns
+            let start = col;
+            while col < width && !is_blank_column(col) {
+                col += 1;
+            }
+
+            let mut operator = None;
+            for op_col in start..col {
+                let symbol = cell(&operators, op_col);
+                if symbol != ' ' {
+                    if operator.is_some() {
+                        return Err(WorksheetError::ExtraOperator { column: op_col + 1 });
+                    }
+                    let op = Operator::from_symbol(symbol)
+                        .ok_or(WorksheetError::UnknownOperator { column: op_col + 1, symbol })?;
+                    operator = Some((op, op_col));
+                }
+            }
+            let (operator, operator_column) = operator.ok_or(WorksheetError::MissingOperator { column: start + 1 })?;
+
+            // The number lines are all ASCII, so can be stored as bytes
+            let lines = lines.iter()
+                .map(|line| (start..col).map(|c| cell(line, c) as u8).collect())
+                .collect();
+            problems.push(Problem { column: start, lines, operator, operator_column });
         }
+
+        Ok(Worksheet { problems })
     }
 
-    result
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
+    //
+    // The sum of the answers to all of the problems.
+    //
+    pub fn total(&self, reading: Reading) -> Result<i128, EvalError> {
+        self.problems.iter().try_fold(0i128, |total, problem| {
+            total.checked_add(problem.evaluate(reading)?)
+                .ok_or(EvalError::Overflow { column: problem.column + 1 })
+        })
+    }
+
+    //
+    // A listing of every problem (see `Problem::audit`), followed by the
+    // grand totals for both readings.  There is no trailing whitespace,
+    // and each problem is rendered independently, so two audits can be
+    // compared with `diff`.
+    //
+    pub fn audit(&self) -> String {
+        let mut result = String::new();
+        for (index, problem) in self.problems.iter().enumerate() {
+            result += &format!("Problem {} (column {})\n", index + 1, problem.column + 1);
+            result += &problem.audit();
+            result += "\n";
+        }
+        for (name, reading) in [("rows", Reading::Rows), ("columns", Reading::Columns)] {
+            let total = match self.total(reading) {
+                Ok(total) => total.to_string(),
+                Err(error) => format!("error: {error}"),
+            };
+            result += &format!("Total ({name}): {total}\n");
+        }
+        result
+    }
 }
 
-pub fn parse_input(input: &str) -> (Vec<Vec<u64>>, Vec<char>) {
-    let (_, (numbers, operators)) = all_consuming(
-        (many1(number_row), operator_row)
-    ).parse(input).expect("Invalid input");
+impl Problem {
+    //
+    // Read the numbers a row at a time (part 1).  Lines with no digits are
+    // skipped.
+    //
+    pub fn row_numbers(&self) -> Result<Vec<u64>, EvalError> {
+        self.lines.iter()
+            .enumerate()
+            .filter_map(|(line_num, line)| {
+                read_number(line.iter().copied())
+                    .map_err(|error| self.read_error(error, |gap| (line_num, gap)))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    //
+    // Read the numbers a column at a time, from the rightmost column to
+    // the leftmost (part 2).  The most significant digit is at the top.
+    // Numbers in a given column may be top-aligned or bottom-aligned, so
+    // just ignore the spaces.  Columns with no digits are skipped.
+    //
+    pub fn column_numbers(&self) -> Result<Vec<u64>, EvalError> {
+        let width = self.lines.first().map_or(0, |line| line.len());
+        (0..width).rev()
+            .filter_map(|col| {
+                read_number(self.lines.iter().map(|line| line[col]))
+                    .map_err(|error| self.read_error(error, |gap| (gap, col)))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    //
+    // Convert an error from `read_number` to an `EvalError`.  `locate`
+    // converts the position of a gap to a (zero-based) line, and a column
+    // within the problem.
+    //
+    fn read_error(&self, error: ReadError, locate: impl Fn(usize) -> (usize, usize)) -> EvalError {
+        let column = self.column + 1;
+        match error {
+            ReadError::Overflow => EvalError::Overflow { column },
+            ReadError::Gap(position) => {
+                let (line, col) = locate(position);
+                EvalError::SplitNumber { column, line: line + 1, gap_column: column + col }
+            }
+        }
+    }
+
+    //
+    // The problem as an expression, with its value, like
+    // `356 * 24 * 1 = 8544`.
+    //
+    pub fn expression(&self, reading: Reading) -> Result<String, EvalError> {
+        let numbers = self.numbers(reading)?;
+        let value = self.evaluate(reading)?;
+        let separator = format!(" {} ", self.operator.symbol());
+        Ok(format!("{} = {value}", numbers.iter().join(&separator)))
+    }
+
+    //
+    // The problem's text, as it appeared in the worksheet, followed by
+    // its expressions for both readings.
+    //
+    pub fn audit(&self) -> String {
+        let mut result = String::new();
+        for line in self.lines.iter() {
+            result += &format!("    {}\n", String::from_utf8_lossy(line).trim_end());
+        }
+        let indent = " ".repeat(self.operator_column - self.column);
+        result += &format!("    {indent}{}\n", self.operator.symbol());
+        for (name, reading) in [("rows:   ", Reading::Rows), ("columns:", Reading::Columns)] {
+            let expression = self.expression(reading).unwrap_or_else(|error| format!("error: {error}"));
+            result += &format!("  {name} {expression}\n");
+        }
+        result
+    }
+
+    pub fn numbers(&self, reading: Reading) -> Result<Vec<u64>, EvalError> {
+        match reading {
+            Reading::Rows => self.row_numbers(),
+            Reading::Columns => self.column_numbers(),
+        }
+    }
+
+    //
+    // Apply the operator to the numbers, read according to `reading`.
+    //
+    pub fn evaluate(&self, reading: Reading) -> Result<i128, EvalError> {
+        let column = self.column + 1;
+        let numbers = self.numbers(reading)?;
+        let (&first, rest) = numbers.split_first().ok_or(EvalError::NoNumbers { column })?;
 
-    (numbers, operators)
+        rest.iter().try_fold(first as i128, |acc, &value| {
+            let value = value as i128;
+            match self.operator.apply(acc, value) {
+                Some(Ok(result)) => Ok(result),
+                Some(Err(_)) => Err(EvalError::InexactDivision { column, dividend: acc, divisor: value }),
+                None if self.operator == Operator::Divide => Err(EvalError::DivisionByZero { column }),
+                None => Err(EvalError::Overflow { column }),
+            }
+        })
+    }
 }
 
-fn number_row(input: &str) -> IResult<&str, Vec<u64>> {
-    delimited(
-        space0,
-        separated_list1(space1, u64),
-        (opt(space0), newline)
-    ).parse(input)
+// Why a sequence of cells couldn't be read as a number
+enum ReadError {
+    // Spaces between digits, starting at the given position
+    Gap(usize),
+    Overflow,
 }
 
-fn operator_row(input: &str) -> IResult<&str, Vec<char>> {
-    delimited(
-        space0,
-        separated_list1(multispace1, alt((char('*'), char('+')))),
-        opt(multispace0)
-    ).parse(input)
+//
+// Combine the digits in `cells` into a number, ignoring leading and
+// trailing spaces.  Returns `Ok(None)` if there are no digits.
+//
+fn read_number(cells: impl Iterator<Item = u8>) -> Result<Option<u64>, ReadError> {
+    let mut result: Option<u64> = None;
+    let mut gap = None;
+    for (position, cell) in cells.enumerate() {
+        if cell == b' ' {
+            if result.is_some() && gap.is_none() {
+                gap = Some(position);
+            }
+        } else if let Some(gap) = gap {
+            return Err(ReadError::Gap(gap));
+        } else {
+            let digit = (cell - b'0') as u64;
+            let value = result.unwrap_or(0).checked_mul(10).and_then(|value| value.checked_add(digit));
+            result = Some(value.ok_or(ReadError::Overflow)?);
+        }
+    }
+    Ok(result)
+}
+
+//
+// Replace tabs with spaces, up to the next multiple of `tab_width` columns.
+//
+fn expand_tabs(line: &str, tab_width: usize) -> Vec<char> {
+    let tab_width = tab_width.max(1);
+    let mut result = Vec::with_capacity(line.len());
+    for ch in line.chars() {
+        if ch == '\t' {
+            let spaces = tab_width - result.len() % tab_width;
+            result.extend(std::iter::repeat_n(' ', spaces));
+        } else {
+            result.push(ch);
+        }
+    }
+    result
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, Worksheet, WorksheetError, Reading, EvalError};
     
     static EXAMPLE_INPUT: &str = "\
 123 328  51 64 
@@ -129,4 +485,215 @@ mod tests {
     fn test_part2_full() {
         assert_eq!(part2(FULL_INPUT), 8674740488592);
     }
+
+    #[test]
+    fn test_worksheet_example() {
+        let worksheet = Worksheet::parse(EXAMPLE_INPUT).unwrap();
+        let problems = worksheet.problems();
+        assert_eq!(problems.len(), 4);
+        assert_eq!(problems.iter().map(|p| p.column).collect::<Vec<_>>(), [0, 4, 8, 12]);
+        assert_eq!(problems.iter().map(|p| p.operator.symbol()).collect::<String>(), "*+*+");
+        assert_eq!(problems[1].lines, [b"328", b"64 ", b"98 "]);
+        assert_eq!(problems[1].row_numbers(), Ok(vec![328, 64, 98]));
+        assert_eq!(problems[1].column_numbers(), Ok(vec![8, 248, 369]));
+        assert_eq!(problems[3].column_numbers(), Ok(vec![4, 431, 623]));
+    }
+
+    #[test]
+    fn test_operator_position() {
+        // The operator need not be in the first column of a problem
+        let input = "\
+12  5
+ 3 17
+ * +
+";
+        let worksheet = Worksheet::parse(input).unwrap();
+        assert_eq!(worksheet.problems().iter().map(|p| p.operator.symbol()).collect::<String>(), "*+");
+        assert_eq!(part1(input), 12 * 3 + 5 + 17);
+        assert_eq!(part2(input), 23 + (57 + 1));
+    }
+
+    #[test]
+    fn test_operator_errors() {
+        assert_eq!(Worksheet::parse(""), Err(WorksheetError::Empty));
+        assert_eq!(Worksheet::parse("12 5\n*\n"), Err(WorksheetError::MissingOperator { column: 4 }));
+        assert_eq!(Worksheet::parse("12 5\n**\n"), Err(WorksheetError::ExtraOperator { column: 2 }));
+    }
+
+    #[test]
+    fn test_operators() {
+        let input = "\
+100 2 7 9 20 2
+ 30 3 5 4  5 3
+  5 5 6 1  4 2
+ -  * < > /  ^
+";
+        let evaluate = |reading| -> Vec<i128> {
+            let worksheet = Worksheet::parse(input).unwrap();
+            worksheet.problems().iter().map(|p| p.evaluate(reading).unwrap()).collect()
+        };
+        assert_eq!(evaluate(Reading::Rows), [65, 30, 5, 9, 1, 64]);
+        assert_eq!(part1(input), 65 + 30 + 5 + 9 + 1 + 64);
+
+        // Reading columns right to left: 5 - 3 - 1, and 54 / 2
+        assert_eq!(evaluate(Reading::Columns), [1, 235, 756, 941, 27, 232]);
+    }
+
+    #[test]
+    fn test_eval_errors() {
+        let worksheet = Worksheet::parse("5 0\n0 5\n/ /\n").unwrap();
+        assert_eq!(worksheet.total(Reading::Rows), Err(EvalError::DivisionByZero { column: 1 }));
+        assert_eq!(worksheet.problems()[1].evaluate(Reading::Rows), Ok(0));
+
+        let worksheet = Worksheet::parse("18446744073709551615\n18446744073709551615\n18446744073709551615\n*\n").unwrap();
+        assert_eq!(worksheet.total(Reading::Rows), Err(EvalError::Overflow { column: 1 }));
+        let worksheet = Worksheet::parse("18446744073709551615\n18446744073709551615\n+\n").unwrap();
+        assert_eq!(worksheet.total(Reading::Rows), Ok(2 * u64::MAX as i128));
+
+        let worksheet = Worksheet::parse("7 8\n2 2\n/ /\n").unwrap();
+        assert_eq!(worksheet.total(Reading::Rows),
+            Err(EvalError::InexactDivision { column: 1, dividend: 7, divisor: 2 }));
+        assert_eq!(worksheet.problems()[1].evaluate(Reading::Rows), Ok(4));
+
+        let worksheet = Worksheet::parse("2\n200\n^\n").unwrap();
+        assert_eq!(worksheet.total(Reading::Rows), Err(EvalError::Overflow { column: 1 }));
+    }
+
+    #[test]
+    fn test_unknown_operator() {
+        let error = Worksheet::parse("1 2\n3 4\n+ %\n").unwrap_err();
+        assert_eq!(error, WorksheetError::UnknownOperator { column: 3, symbol: '%' });
+        assert_eq!(error.to_string(), "unknown operator '%' at column 3");
+    }
+
+    #[test]
+    fn test_ragged_lines() {
+        // The example, with trailing spaces stripped, and a blank line at the end
+        let input = "123 328  51 64\n 45 64  387 23\n  6 98  215 314\n*   +   *   +\n\n";
+        assert_eq!(part1(input), 4277556);
+        assert_eq!(part2(input), 3263827);
+
+        // A short number line is padded with spaces
+        let worksheet = Worksheet::parse("12 345\n3\n+  *\n").unwrap();
+        assert_eq!(worksheet.problems()[1].lines, [b"345", b"   "]);
+        assert_eq!(worksheet.total(Reading::Rows), Ok(15 + 345));
+    }
+
+    #[test]
+    fn test_tabs() {
+        let input = "123\t 5\n 45\t17\n*\t+\n";
+        let worksheet = Worksheet::parse(input).unwrap();
+        assert_eq!(worksheet.problems().iter().map(|p| p.column).collect::<Vec<_>>(), [0, 8]);
+        assert_eq!(worksheet.total(Reading::Rows), Ok(123 * 45 + 5 + 17));
+
+        let worksheet = Worksheet::parse_with_tab_width(input, 4).unwrap();
+        assert_eq!(worksheet.problems().iter().map(|p| p.column).collect::<Vec<_>>(), [0, 4]);
+        // 35 * 24 * 1
+        assert_eq!(worksheet.total(Reading::Columns), Ok(35 * 24 + 57 + 1));
+    }
+
+    #[test]
+    fn test_invalid_characters() {
+        let error = Worksheet::parse("12 34\n5x 6\n+  *\n").unwrap_err();
+        assert_eq!(error, WorksheetError::InvalidCharacter { line: 2, column: 2, found: 'x' });
+        assert_eq!(error.to_string(), "line 2, column 2: expected a digit or space, found 'x'");
+        assert_eq!(Worksheet::parse("12 3½\n+  *\n"),
+            Err(WorksheetError::InvalidCharacter { line: 1, column: 5, found: '½' }));
+        assert_eq!(Worksheet::parse("12 34\n+  ×\n"),
+            Err(WorksheetError::UnknownOperator { column: 4, symbol: '×' }));
+    }
+
+    #[test]
+    fn test_split_numbers() {
+        // Row 0 of the first problem has a gap (fine when reading columns)
+        let worksheet = Worksheet::parse("1 2 7\n345 8\n+   +\n").unwrap();
+        let error = worksheet.total(Reading::Rows).unwrap_err();
+        assert_eq!(error, EvalError::SplitNumber { column: 1, line: 1, gap_column: 2 });
+        assert_eq!(error.to_string(), "problem at column 1: space between digits at line 1, column 2");
+        assert_eq!(worksheet.total(Reading::Columns), Ok(25 + 4 + 13 + 78));
+
+        // Column 1 has a gap (fine when reading rows)
+        let worksheet = Worksheet::parse("12 7\n3   \n45 9\n+  +\n").unwrap();
+        assert_eq!(worksheet.total(Reading::Rows), Ok(12 + 3 + 45 + 7 + 9));
+        assert_eq!(worksheet.total(Reading::Columns),
+            Err(EvalError::SplitNumber { column: 1, line: 2, gap_column: 2 }));
+    }
+
+    #[test]
+    fn test_number_overflow() {
+        let worksheet = Worksheet::parse("123456789012345678901\n+\n").unwrap();
+        assert_eq!(worksheet.total(Reading::Rows), Err(EvalError::Overflow { column: 1 }));
+    }
+
+    #[test]
+    fn test_expression() {
+        let worksheet = Worksheet::parse(EXAMPLE_INPUT).unwrap();
+        let problem = &worksheet.problems()[0];
+        assert_eq!(problem.expression(Reading::Rows), Ok("123 * 45 * 6 = 33210".to_string()));
+        assert_eq!(problem.expression(Reading::Columns), Ok("356 * 24 * 1 = 8544".to_string()));
+    }
+
+    #[test]
+    fn test_audit() {
+        let worksheet = Worksheet::parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(worksheet.audit(), "\
+Problem 1 (column 1)
+    123
+     45
+      6
+    *
+  rows:    123 * 45 * 6 = 33210
+  columns: 356 * 24 * 1 = 8544
+
+Problem 2 (column 5)
+    328
+    64
+    98
+    +
+  rows:    328 + 64 + 98 = 490
+  columns: 8 + 248 + 369 = 625
+
+Problem 3 (column 9)
+     51
+    387
+    215
+    *
+  rows:    51 * 387 * 215 = 4243455
+  columns: 175 * 581 * 32 = 3253600
+
+Problem 4 (column 13)
+    64
+    23
+    314
+    +
+  rows:    64 + 23 + 314 = 401
+  columns: 4 + 431 + 623 = 1058
+
+Total (rows): 4277556
+Total (columns): 3263827
+");
+    }
+
+    #[test]
+    fn test_audit_errors() {
+        let worksheet = Worksheet::parse("1 2 7\n345 0\n +  /\n").unwrap();
+        assert_eq!(worksheet.audit(), "\
+Problem 1 (column 1)
+    1 2
+    345
+     +
+  rows:    error: problem at column 1: space between digits at line 1, column 2
+  columns: 25 + 4 + 13 = 42
+
+Problem 2 (column 5)
+    7
+    0
+    /
+  rows:    error: problem at column 5 divides by zero
+  columns: 70 = 70
+
+Total (rows): error: problem at column 1: space between digits at line 1, column 2
+Total (columns): 112
+");
+    }
 }
@@ -1,4 +1,4 @@
-use day06::{part1, part2};
+use day06::{part1, part2, parse_input};
 
 fn main() {
     let input = include_str!("../input.txt");
@@ -8,4 +8,10 @@ fn main() {
 
     let result2 = part2(input);
     println!("Part 2: {result2}");
+
+    // With `--audit`, show how each problem was read and evaluated.
+    if std::env::args().any(|arg| arg == "--audit") {
+        println!();
+        print!("{}", parse_input(input).audit());
+    }
 }
@@ -1,4 +1,4 @@
-use day07::{ part1, part2, both, both_array };
+use day07::{ part1, part2, both, both_array, manifold::{Edge, Manifold}, timeline::TimelineIndex };
 
 fn main() {
     divan::main();
@@ -8,20 +8,32 @@ const INPUT: &str = include_str!("../input.txt");
 
 #[divan::bench]
 fn bench_part1() {
-    part1(INPUT);
+    part1(INPUT, Edge::Lose);
 }
 
 #[divan::bench]
 fn bench_part2() {
-    part2(INPUT);
+    part2(INPUT, Edge::Lose);
 }
 
 #[divan::bench]
 fn bench_both() {
-    both(INPUT);
+    both(INPUT, Edge::Lose);
 }
 
 #[divan::bench]
 fn bench_both_array() {
-    both_array(INPUT);
+    both_array(INPUT, Edge::Lose);
+}
+
+#[divan::bench]
+fn bench_simulate() {
+    Manifold::parse(INPUT).simulate();
+}
+
+#[divan::bench]
+fn bench_timeline_sample(bencher: divan::Bencher) {
+    let manifold = Manifold::parse(INPUT);
+    let index = TimelineIndex::new(&manifold);
+    bencher.bench(|| index.sample(divan::black_box(0x9e37_79b9_7f4a_7c15)));
 }
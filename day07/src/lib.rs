@@ -1,6 +1,26 @@
 use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+use manifold::{Edge, Side};
 
-pub fn part1(input: &str) -> u32 {
+//
+// The width of the manifold.  Lines may be of different lengths, so it
+// is as wide as its longest line.
+//
+fn width(input: &str) -> usize {
+    input.lines().map(str::len).max().unwrap_or(0)
+}
+
+//
+// Where the two beams from a splitter go: the columns either side of it,
+// with `edge` deciding what happens past the first or last column.  A
+// beam that leaves the manifold (`Err`) still counts as a timeline, as
+// it does in `Simulation::timelines`.
+//
+fn beside(splitter: usize, width: usize, edge: Edge) -> [Result<usize, Side>; 2] {
+    [edge.shift(splitter, -1, width), edge.shift(splitter, 1, width)]
+}
+
+pub fn part1(input: &str, edge: Edge) -> u32 {
+    let width = width(input);
     let mut lines = input.lines();
     let first_line = lines.next().unwrap();
     let starting_col = first_line.find('S').unwrap();
@@ -13,8 +33,7 @@ pub fn part1(input: &str) -> u32 {
         for (splitter, _) in line.match_indices('^') {
             if columns.remove(&splitter) {
                 splits += 1;
-                columns.insert(splitter - 1);
-                columns.insert(splitter + 1);
+                columns.extend(beside(splitter, width, edge).into_iter().flatten());
             }
         }
     }
@@ -22,26 +41,33 @@ pub fn part1(input: &str) -> u32 {
     splits
 }
 
-pub fn part2(input: &str) -> u64 {
+pub fn part2(input: &str, edge: Edge) -> u64 {
+    let width = width(input);
     let mut lines = input.lines();
     let first_line = lines.next().unwrap();
     let starting_col = first_line.find('S').unwrap();
     let mut columns = HashMap::<usize, u64>::default();
     columns.insert(starting_col, 1);
+    let mut escaped = 0;
 
     for line in lines {
         for (splitter, _) in line.match_indices('^') {
             if let Some(count) = columns.remove(&splitter) {
-                *columns.entry(splitter - 1).or_default() += count;
-                *columns.entry(splitter + 1).or_default() += count;
+                for beam in beside(splitter, width, edge) {
+                    match beam {
+                        Ok(beam) => *columns.entry(beam).or_default() += count,
+                        Err(_) => escaped += count,
+                    }
+                }
             }
         }
     }
 
-    columns.values().sum()
+    columns.values().sum::<u64>() + escaped
 }
 
-pub fn both(input: &str) -> (u32, u64) {
+pub fn both(input: &str, edge: Edge) -> (u32, u64) {
+    let width = width(input);
     let mut lines = input.lines();
     let first_line = lines.next().unwrap();
     let starting_col = first_line.find('S').unwrap();
@@ -49,88 +75,1031 @@ pub fn both(input: &str) -> (u32, u64) {
     columns.insert(starting_col, 1);
 
     let mut splits = 0;
+    let mut escaped = 0;
 
     for line in lines {
         for (splitter, _) in line.match_indices('^') {
             if let Some(count) = columns.remove(&splitter) {
                 splits += 1;
-                *columns.entry(splitter - 1).or_default() += count;
-                *columns.entry(splitter + 1).or_default() += count;
+                for beam in beside(splitter, width, edge) {
+                    match beam {
+                        Ok(beam) => *columns.entry(beam).or_default() += count,
+                        Err(_) => escaped += count,
+                    }
+                }
             }
         }
     }
 
-    (splits, columns.values().sum())
+    (splits, columns.values().sum::<u64>() + escaped)
 }
 
-pub fn both_array(input: &str) -> (u32, u64) {
+pub fn both_array(input: &str, edge: Edge) -> (u32, u64) {
     let mut lines = input.lines();
     let first_line = lines.next().unwrap();
-    let mut columns: Vec<u64> = first_line
-        .chars()
-        .map(|ch| (ch == 'S') as u64)
-        .collect();
+    let mut columns: Vec<u64> = vec![0; width(input)];
+    for (col, ch) in first_line.chars().enumerate() {
+        columns[col] = (ch == 'S') as u64;
+    }
 
     let mut splits = 0;
+    let mut escaped = 0;
 
     for line in lines {
         for (splitter, _) in line.match_indices('^') {
             let column_count = columns[splitter];
             if column_count != 0 {
                 splits += 1;
-                columns[splitter - 1] += column_count;
-                columns[splitter + 1] += column_count;
+                // Cleared first, since a clamped beam can land back here
                 columns[splitter] = 0;
+                for beam in beside(splitter, columns.len(), edge) {
+                    match beam {
+                        Ok(beam) => columns[beam] += column_count,
+                        Err(_) => escaped += column_count,
+                    }
+                }
             }
         }
     }
 
-    (splits, columns.into_iter().sum())
+    (splits, columns.into_iter().sum::<u64>() + escaped)
+}
+
+//
+// A full simulation of the manifold, recording where the beams go, not
+// just how many there are.  The beams move down a row at a time, with
+// one count of timelines per column, as in `both_array`.
+//
+pub mod manifold {
+    use rustc_hash::FxHashMap;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Side {
+        Left,
+        Right,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Element {
+        Empty,
+        // Starts one timeline, heading down from this cell
+        Source,
+        // Stops any beam that reaches it
+        Absorber,
+        // Moves a beam one column to the given side
+        Deflector(Side),
+        // Splits a beam into this many, spread evenly either side
+        Splitter(usize),
+    }
+
+    impl Element {
+        //
+        // Where beams reaching this element go, as column offsets, or `None`
+        // if beams pass straight through.  A splitter with an odd fan-out
+        // also sends a beam on down its own column.
+        //
+        pub fn offsets(&self) -> Option<Vec<isize>> {
+            match *self {
+                Element::Empty | Element::Source => None,
+                Element::Absorber => Some(Vec::new()),
+                Element::Deflector(Side::Left) => Some(vec![-1]),
+                Element::Deflector(Side::Right) => Some(vec![1]),
+                Element::Splitter(fan_out) => {
+                    let reach = (fan_out / 2) as isize;
+                    Some((-reach ..= reach)
+                        .filter(|&offset| offset != 0 || fan_out % 2 == 1)
+                        .collect())
+                }
+            }
+        }
+    }
+
+    //
+    // What happens to a beam sent sideways past the first or last column:
+    // it leaves the manifold, comes back in on the other side, or stops
+    // at the edge.  `part1`, `part2`, `both` and `both_array` take one
+    // too; the puzzle input never splits a beam off the edge, so any of
+    // them gives the puzzle's answers.
+    //
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum Edge {
+        #[default]
+        Lose,
+        Wrap,
+        Clamp,
+    }
+
+    impl Edge {
+        //
+        // The column a beam moves to from `col` in a manifold `width`
+        // columns wide, or the side it leaves by.
+        //
+        pub fn shift(self, col: usize, offset: isize, width: usize) -> Result<usize, Side> {
+            let width = width as isize;
+            let target = col as isize + offset;
+            if (0 .. width).contains(&target) {
+                return Ok(target as usize);
+            }
+            match self {
+                Edge::Lose if target < 0 => Err(Side::Left),
+                Edge::Lose => Err(Side::Right),
+                Edge::Wrap => Ok(target.rem_euclid(width) as usize),
+                Edge::Clamp => Ok(target.clamp(0, width - 1) as usize),
+            }
+        }
+    }
+
+    //
+    // Beams that left the manifold on one side, and the number of
+    // timelines they carried.
+    //
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct Escapes {
+        pub beams: u32,
+        pub timelines: u64,
+    }
+
+    //
+    // Which symbols in the input mean which elements.  Anything not in
+    // the legend is empty space.
+    //
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Legend {
+        symbols: FxHashMap<char, Element>,
+    }
+
+    impl Legend {
+        pub fn new() -> Legend {
+            Legend { symbols: FxHashMap::default() }
+        }
+
+        pub fn with(mut self, symbol: char, element: Element) -> Legend {
+            self.symbols.insert(symbol, element);
+            self
+        }
+
+        pub fn get(&self, symbol: char) -> Element {
+            self.symbols.get(&symbol).copied().unwrap_or(Element::Empty)
+        }
+    }
+
+    //
+    // The puzzle's `S` and `^`, plus `#` absorbers, `<` and `>` deflectors,
+    // and digits `3` to `9` for splitters with that fan-out.
+    //
+    impl Default for Legend {
+        fn default() -> Legend {
+            let legend = Legend::new()
+                .with('S', Element::Source)
+                .with('^', Element::Splitter(2))
+                .with('#', Element::Absorber)
+                .with('<', Element::Deflector(Side::Left))
+                .with('>', Element::Deflector(Side::Right));
+            ('3' ..= '9').fold(legend, |legend, digit| {
+                legend.with(digit, Element::Splitter(digit as usize - '0' as usize))
+            })
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Manifold {
+        symbols: Vec<Vec<char>>,
+        cells: Vec<Vec<Element>>,
+        width: usize,
+        edge: Edge,
+    }
+
+    //
+    // A vertical piece of beam, occupying `column` from `start_row` to
+    // `end_row` inclusive.  A beam starts at a source, or beside the
+    // element that sent it sideways, and ends just above the element
+    // that stops or redirects it (or at the bottom of the manifold).
+    //
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Segment {
+        pub column: usize,
+        pub start_row: usize,
+        pub end_row: usize,
+    }
+
+    //
+    // The results of a simulation.  Splitters are (row, column), in the
+    // order they are hit (or appear in the manifold, for ones never hit).
+    // `exits[col]` is the number of timelines whose beam leaves the
+    // bottom row in column `col`, `absorbed` the number that end in an
+    // absorber, and `left` and `right` those that leave by the sides.
+    //
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Simulation {
+        pub segments: Vec<Segment>,
+        pub splitters_hit: Vec<(usize, usize)>,
+        pub splitters_missed: Vec<(usize, usize)>,
+        pub exits: Vec<u64>,
+        pub absorbed: u64,
+        pub left: Escapes,
+        pub right: Escapes,
+    }
+
+    impl Manifold {
+        pub fn parse(input: &str) -> Manifold {
+            Manifold::parse_with(input, &Legend::default())
+        }
+
+        //
+        // Short lines are padded with empty space to the longest.
+        //
+        pub fn parse_with(input: &str, legend: &Legend) -> Manifold {
+            let mut symbols: Vec<Vec<char>> = input.lines()
+                .map(|line| line.chars().collect())
+                .collect();
+            let width = symbols.iter().map(Vec::len).max().unwrap_or(0);
+            for row in symbols.iter_mut() {
+                row.resize(width, '.');
+            }
+            let cells = symbols.iter()
+                .map(|row| row.iter().map(|&symbol| legend.get(symbol)).collect())
+                .collect();
+            Manifold { symbols, cells, width, edge: Edge::default() }
+        }
+
+        pub fn with_edge(mut self, edge: Edge) -> Manifold {
+            self.edge = edge;
+            self
+        }
+
+        pub fn edge(&self) -> Edge {
+            self.edge
+        }
+
+        //
+        // The column a beam moves to from `col`, or the side it leaves by.
+        //
+        pub fn shift(&self, col: usize, offset: isize) -> Result<usize, Side> {
+            self.edge.shift(col, offset, self.width)
+        }
+
+        pub fn rows(&self) -> usize {
+            self.cells.len()
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn get(&self, row: usize, col: usize) -> Element {
+            self.cells[row][col]
+        }
+
+        pub fn sources(&self) -> Vec<(usize, usize)> {
+            self.positions(|element| element == Element::Source)
+        }
+
+        pub fn splitters(&self) -> Vec<(usize, usize)> {
+            self.positions(|element| matches!(element, Element::Splitter(_)))
+        }
+
+        fn positions(&self, wanted: impl Fn(Element) -> bool) -> Vec<(usize, usize)> {
+            self.cells.iter().enumerate()
+                .flat_map(|(row, cells)| cells.iter().enumerate()
+                    .filter(|&(_, &element)| wanted(element))
+                    .map(move |(col, _)| (row, col)))
+                .collect()
+        }
+
+        //
+        // As in `both_array`, each row is worked through from left to right,
+        // updating the columns as it goes.  So a beam sent right onto
+        // another element in the same row is acted on again straight away,
+        // but a beam sent left (into a column already done) is not acted
+        // on until the next row.
+        //
+        pub fn simulate(&self) -> Simulation {
+            // Timelines in each column, and the row where the beam in that
+            // column (if any) started
+            let mut columns = vec![0u64; self.width];
+            let mut started: Vec<Option<usize>> = vec![None; self.width];
+            let mut segments = Vec::new();
+            let mut splitters_hit = Vec::new();
+            let mut splitters_missed = Vec::new();
+            let mut absorbed = 0;
+            let mut left = Escapes::default();
+            let mut right = Escapes::default();
+
+            for (row, cells) in self.cells.iter().enumerate() {
+                for (col, &element) in cells.iter().enumerate() {
+                    if element == Element::Source {
+                        columns[col] += 1;
+                        started[col].get_or_insert(row);
+                        continue;
+                    }
+                    let Some(offsets) = element.offsets() else { continue };
+
+                    let count = columns[col];
+                    let is_splitter = matches!(element, Element::Splitter(_));
+                    if count == 0 {
+                        if is_splitter {
+                            splitters_missed.push((row, col));
+                        }
+                        continue;
+                    }
+                    if is_splitter {
+                        splitters_hit.push((row, col));
+                    }
+
+                    columns[col] = 0;
+                    if let Some(start_row) = started[col].take() && start_row < row {
+                        segments.push(Segment { column: col, start_row, end_row: row - 1 });
+                    }
+                    if offsets.is_empty() {
+                        absorbed += count;
+                    }
+                    for offset in offsets {
+                        match self.shift(col, offset) {
+                            Ok(beam) => {
+                                columns[beam] += count;
+                                started[beam].get_or_insert(row);
+                            }
+                            Err(side) => {
+                                let escapes = match side {
+                                    Side::Left => &mut left,
+                                    Side::Right => &mut right,
+                                };
+                                escapes.beams += 1;
+                                escapes.timelines += count;
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Beams still going leave the bottom of the manifold
+            let last_row = self.rows().saturating_sub(1);
+            for (col, start_row) in started.into_iter().enumerate() {
+                if let Some(start_row) = start_row {
+                    segments.push(Segment { column: col, start_row, end_row: last_row });
+                }
+            }
+            segments.sort_by_key(|segment| (segment.start_row, segment.column));
+
+            Simulation {
+                segments, splitters_hit, splitters_missed, exits: columns, absorbed, left, right,
+            }
+        }
+    }
+
+    impl Simulation {
+        pub fn splits(&self) -> u32 {
+            self.splitters_hit.len() as u32
+        }
+
+        //
+        // Every timeline ends either leaving the bottom or a side, or in an
+        // absorber.
+        //
+        pub fn timelines(&self) -> u64 {
+            self.exits.iter().sum::<u64>() + self.absorbed + self.left.timelines + self.right.timelines
+        }
+
+        //
+        // Draw the manifold, with `|` wherever there is a beam in empty
+        // space.
+        //
+        pub fn render(&self, manifold: &Manifold) -> String {
+            let mut grid = manifold.symbols.clone();
+
+            for segment in self.segments.iter() {
+                let rows = segment.start_row ..= segment.end_row;
+                for (line, cells) in grid[rows.clone()].iter_mut().zip(&manifold.cells[rows]) {
+                    if cells[segment.column] == Element::Empty {
+                        line[segment.column] = '|';
+                    }
+                }
+            }
+
+            grid.into_iter().map(|row| row.into_iter().collect::<String>() + "\n").collect()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{Edge, Element, Escapes, Legend, Manifold, Segment, Side};
+
+        static EXAMPLE_INPUT: &str = include_str!("../example.txt");
+
+        #[test]
+        fn test_simulate_example() {
+            let manifold = Manifold::parse(EXAMPLE_INPUT);
+            let simulation = manifold.simulate();
+            assert_eq!(simulation.splits(), 21);
+            assert_eq!(simulation.timelines(), 40);
+            assert_eq!(simulation.splitters_missed, [(14, 9)]);
+            assert_eq!(simulation.exits, [1, 0, 2, 0, 10, 0, 11, 0, 11, 0, 2, 1, 1, 0, 1]);
+            assert_eq!(simulation.segments[..3], [
+                Segment { column: 7, start_row: 0, end_row: 1 },
+                Segment { column: 6, start_row: 2, end_row: 3 },
+                Segment { column: 8, start_row: 2, end_row: 3 },
+            ]);
+        }
+
+        #[test]
+        fn test_render_example() {
+            let manifold = Manifold::parse(EXAMPLE_INPUT);
+            assert_eq!(manifold.simulate().render(&manifold), "\
+.......S.......
+.......|.......
+......|^|......
+......|.|......
+.....|^|^|.....
+.....|.|.|.....
+....|^|^|^|....
+....|.|.|.|....
+...|^|^|||^|...
+...|.|.|||.|...
+..|^|^|||^|^|..
+..|.|.|||.|.|..
+.|^|||^||.||^|.
+.|.|||.||.||.|.
+|^|^|^|^|^|||^|
+|.|.|.|.|.|||.|
+");
+        }
+
+        #[test]
+        fn test_splitter_offsets() {
+            assert_eq!(Element::Splitter(2).offsets(), Some(vec![-1, 1]));
+            assert_eq!(Element::Splitter(3).offsets(), Some(vec![-1, 0, 1]));
+            assert_eq!(Element::Splitter(4).offsets(), Some(vec![-2, -1, 1, 2]));
+            assert_eq!(Element::Absorber.offsets(), Some(vec![]));
+            assert_eq!(Element::Empty.offsets(), None);
+        }
+
+        #[test]
+        fn test_several_sources() {
+            let manifold = Manifold::parse("\
+.S...S.
+.......
+.^...^.
+...S...
+.......
+");
+            let simulation = manifold.simulate();
+            assert_eq!(manifold.sources(), [(0, 1), (0, 5), (3, 3)]);
+            assert_eq!(simulation.splits(), 2);
+            assert_eq!(simulation.timelines(), 5);
+            assert_eq!(simulation.exits, [1, 0, 1, 1, 1, 0, 1]);
+        }
+
+        #[test]
+        fn test_absorbers_and_deflectors() {
+            let manifold = Manifold::parse("\
+...S...
+.......
+...^...
+..#.>..
+.......
+");
+            let simulation = manifold.simulate();
+            assert_eq!(simulation.absorbed, 1);
+            assert_eq!(simulation.exits, [0, 0, 0, 0, 0, 1, 0]);
+            assert_eq!(simulation.timelines(), 2);
+            assert_eq!(simulation.render(&manifold), "\
+...S...
+...|...
+..|^|..
+..#.>|.
+.....|.
+");
+        }
+
+        #[test]
+        fn test_fan_out() {
+            let manifold = Manifold::parse("\
+....S....
+.........
+....5....
+.........
+..^...^..
+.........
+");
+            let simulation = manifold.simulate();
+            assert_eq!(simulation.splits(), 3);
+            assert_eq!(simulation.exits, [0, 1, 0, 2, 1, 2, 0, 1, 0]);
+            assert_eq!(simulation.timelines(), 7);
+        }
+
+        #[test]
+        fn test_custom_legend() {
+            let legend = Legend::new()
+                .with('*', Element::Source)
+                .with('v', Element::Splitter(2))
+                .with('[', Element::Deflector(Side::Left));
+            let manifold = Manifold::parse_with("\
+..*..
+..v..
+.[...
+.....
+", &legend);
+            let simulation = manifold.simulate();
+            assert_eq!(simulation.splitters_hit, [(1, 2)]);
+            assert_eq!(simulation.exits, [1, 0, 0, 1, 0]);
+        }
+
+        static EDGE_INPUT: &str = "\
+S...S
+.....
+^...^
+.....
+";
+
+        #[test]
+        fn test_edge_lose() {
+            let simulation = Manifold::parse(EDGE_INPUT).simulate();
+            assert_eq!(simulation.exits, [0, 1, 0, 1, 0]);
+            assert_eq!(simulation.left, Escapes { beams: 1, timelines: 1 });
+            assert_eq!(simulation.right, Escapes { beams: 1, timelines: 1 });
+            assert_eq!(simulation.timelines(), 4);
+        }
+
+        #[test]
+        fn test_edge_wrap() {
+            let simulation = Manifold::parse("S....\n.....\n^....\n").with_edge(Edge::Wrap).simulate();
+            assert_eq!(simulation.exits, [0, 1, 0, 0, 1]);
+            assert_eq!(simulation.left, Escapes::default());
+        }
+
+        #[test]
+        fn test_edge_clamp() {
+            let simulation = Manifold::parse(EDGE_INPUT).with_edge(Edge::Clamp).simulate();
+            assert_eq!(simulation.exits, [1, 1, 0, 1, 1]);
+            assert_eq!(simulation.left, Escapes::default());
+            assert_eq!(simulation.timelines(), 4);
+        }
+
+        #[test]
+        fn test_ragged_lines() {
+            let manifold = Manifold::parse("\
+..S
+...
+..^.
+.
+");
+            assert_eq!(manifold.width(), 4);
+            let simulation = manifold.simulate();
+            assert_eq!(simulation.exits, [0, 1, 0, 1]);
+            assert_eq!(simulation.render(&manifold), "\
+..S.
+..|.
+.|^|
+.|.|
+");
+        }
+    }
+}
+
+//
+// The individual timelines through a manifold.  Each one follows a
+// single beam from its source, making a choice at every splitter, and
+// they are numbered in lexicographic order: by source, then by the
+// choices made, leftmost first.  The number of timelines from every
+// cell is worked out up front, so any timeline can be found directly
+// from its index.
+//
+pub mod timeline {
+    use crate::manifold::{Manifold, Side};
+
+    //
+    // The branch a timeline took at a splitter, as a column offset.
+    //
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Choice {
+        pub row: usize,
+        pub column: usize,
+        pub offset: isize,
+    }
+
+    impl Choice {
+        pub fn side(&self) -> Option<Side> {
+            match self.offset {
+                ..0 => Some(Side::Left),
+                0 => None,
+                1.. => Some(Side::Right),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum End {
+        // Left the bottom row in this column
+        Bottom(usize),
+        // Stopped by the absorber at (row, column)
+        Absorbed(usize, usize),
+        // Sent off the side of the manifold
+        Escaped(Side),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Timeline {
+        pub source: (usize, usize),
+        pub choices: Vec<Choice>,
+        pub end: End,
+    }
+
+    pub struct TimelineIndex<'a> {
+        manifold: &'a Manifold,
+        sources: Vec<(usize, usize)>,
+        // `ways[row][col]` is the number of timelines for a beam in `col`
+        // that has yet to be acted on by `row`
+        ways: Vec<Vec<u64>>,
+    }
+
+    impl<'a> TimelineIndex<'a> {
+        pub fn new(manifold: &'a Manifold) -> TimelineIndex<'a> {
+            let rows = manifold.rows();
+            let mut ways = vec![vec![1u64; manifold.width()]; rows + 1];
+            for row in (0 .. rows).rev() {
+                let (above, below) = ways.split_at_mut(row + 1);
+                let (here, below) = (&mut above[row], &below[0]);
+
+                // Right to left, since a beam sent right can be acted on
+                // again in the same row
+                for col in (0 .. manifold.width()).rev() {
+                    let count = match manifold.get(row, col).offsets() {
+                        None => below[col],
+                        Some(offsets) if offsets.is_empty() => 1,
+                        Some(offsets) => offsets.into_iter()
+                            .map(|offset| match manifold.shift(col, offset) {
+                                Ok(next) if next > col => here[next],
+                                Ok(next) => below[next],
+                                Err(_) => 1,
+                            })
+                            .sum(),
+                    };
+                    here[col] = count;
+                }
+            }
+            TimelineIndex { manifold, sources: manifold.sources(), ways }
+        }
+
+        //
+        // The number of timelines.  Every way a timeline can end counts:
+        // leaving the bottom row, stopping in an absorber, or being sent
+        // off the side of the manifold.  This is the same total as
+        // `Simulation::timelines` and `part2`.
+        //
+        pub fn len(&self) -> u64 {
+            self.sources.iter().map(|&(row, col)| self.ways[row + 1][col]).sum()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        //
+        // The timeline with the given index, or `None` if there aren't
+        // that many.
+        //
+        pub fn timeline(&self, mut index: u64) -> Option<Timeline> {
+            for &source in self.sources.iter() {
+                let count = self.ways[source.0 + 1][source.1];
+                if index < count {
+                    return Some(self.follow(source, index));
+                }
+                index -= count;
+            }
+            None
+        }
+
+        fn follow(&self, source: (usize, usize), mut index: u64) -> Timeline {
+            let mut choices = Vec::new();
+            let (mut row, mut col) = (source.0 + 1, source.1);
+
+            let end = loop {
+                if row == self.manifold.rows() {
+                    break End::Bottom(col);
+                }
+                let Some(offsets) = self.manifold.get(row, col).offsets() else {
+                    row += 1;
+                    continue;
+                };
+                if offsets.is_empty() {
+                    break End::Absorbed(row, col);
+                }
+
+                // Skip over the timelines of the branches before ours
+                let decision = offsets.len() > 1;
+                let mut branches = offsets.into_iter().map(|offset| {
+                    let next = self.manifold.shift(col, offset);
+                    let count = next.map_or(1, |next| self.ways[Self::next_row(row, col, next)][next]);
+                    (offset, next, count)
+                });
+                let (offset, next) = loop {
+                    let (offset, next, count) = branches.next().expect("Index within branch counts");
+                    if index < count {
+                        break (offset, next);
+                    }
+                    index -= count;
+                };
+
+                if decision {
+                    choices.push(Choice { row, column: col, offset });
+                }
+                match next {
+                    Ok(next) => (row, col) = (Self::next_row(row, col, next), next),
+                    Err(side) => break End::Escaped(side),
+                }
+            };
+
+            Timeline { source, choices, end }
+        }
+
+        //
+        // The row that next acts on a beam moved from `col` to `next`.  As
+        // in the simulation, moving right keeps it in the same row.
+        //
+        fn next_row(row: usize, col: usize, next: usize) -> usize {
+            if next > col { row } else { row + 1 }
+        }
+
+        //
+        // All the timelines in order, each found only when needed.
+        //
+        pub fn iter(&self) -> Iter<'_, 'a> {
+            Iter { index: self, next: 0, end: self.len() }
+        }
+
+        //
+        // A timeline chosen uniformly from all of them, so paths are
+        // weighted by how many timelines follow them, given a uniformly
+        // random `u64`.
+        //
+        pub fn sample(&self, random: u64) -> Option<Timeline> {
+            let index = (random as u128 * self.len() as u128) >> 64;
+            self.timeline(index as u64)
+        }
+    }
+
+    pub struct Iter<'i, 'a> {
+        index: &'i TimelineIndex<'a>,
+        next: u64,
+        end: u64,
+    }
+
+    impl Iterator for Iter<'_, '_> {
+        type Item = Timeline;
+
+        fn next(&mut self) -> Option<Timeline> {
+            if self.next == self.end {
+                return None;
+            }
+            self.next += 1;
+            self.index.timeline(self.next - 1)
+        }
+
+        fn nth(&mut self, n: usize) -> Option<Timeline> {
+            self.next = self.next.saturating_add(n as u64).min(self.end);
+            self.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let remaining = usize::try_from(self.end - self.next).ok();
+            (remaining.unwrap_or(usize::MAX), remaining)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{Choice, End, TimelineIndex};
+        use crate::manifold::{Edge, Manifold, Side};
+        use crate::{part2, both_array};
+
+        static EXAMPLE_INPUT: &str = include_str!("../example.txt");
+
+        #[test]
+        fn test_count_example() {
+            let manifold = Manifold::parse(EXAMPLE_INPUT);
+            assert_eq!(TimelineIndex::new(&manifold).len(), 40);
+        }
+
+        #[test]
+        fn test_first_and_last_example() {
+            let manifold = Manifold::parse(EXAMPLE_INPUT);
+            let index = TimelineIndex::new(&manifold);
+
+            let first = index.timeline(0).unwrap();
+            assert_eq!(first.source, (0, 7));
+            assert_eq!(first.choices.len(), 7);
+            assert!(first.choices.iter().all(|choice| choice.side() == Some(Side::Left)));
+            assert_eq!(first.choices[0], Choice { row: 2, column: 7, offset: -1 });
+            assert_eq!(first.end, End::Bottom(0));
+
+            let last = index.timeline(39).unwrap();
+            assert!(last.choices.iter().all(|choice| choice.side() == Some(Side::Right)));
+            assert_eq!(last.end, End::Bottom(14));
+
+            assert_eq!(index.timeline(40), None);
+        }
+
+        #[test]
+        fn test_iter_matches_simulation() {
+            let manifold = Manifold::parse(EXAMPLE_INPUT);
+            let index = TimelineIndex::new(&manifold);
+            let mut exits = vec![0u64; manifold.width()];
+            let mut previous: Option<Vec<isize>> = None;
+            for timeline in index.iter() {
+                let End::Bottom(col) = timeline.end else { panic!("Timeline left the bottom") };
+                exits[col] += 1;
+
+                // Lexicographic order of the choices
+                let offsets: Vec<isize> = timeline.choices.iter().map(|choice| choice.offset).collect();
+                if let Some(previous) = previous {
+                    assert!(previous < offsets);
+                }
+                previous = Some(offsets);
+            }
+            assert_eq!(exits, manifold.simulate().exits);
+            assert_eq!(index.iter().nth(39), index.timeline(39));
+            assert_eq!(index.iter().nth(40), None);
+        }
+
+        #[test]
+        fn test_sample() {
+            let manifold = Manifold::parse(EXAMPLE_INPUT);
+            let index = TimelineIndex::new(&manifold);
+            assert_eq!(index.sample(0), index.timeline(0));
+            assert_eq!(index.sample(u64::MAX), index.timeline(39));
+            assert_eq!(index.sample(1 << 63), index.timeline(20));
+        }
+
+        #[test]
+        fn test_ends() {
+            let manifold = Manifold::parse("\
+...S.
+.....
+...^.
+..#.^
+.....
+");
+            let index = TimelineIndex::new(&manifold);
+            let ends: Vec<End> = index.iter().map(|timeline| timeline.end).collect();
+            assert_eq!(ends, [End::Absorbed(3, 2), End::Bottom(3), End::Escaped(Side::Right)]);
+            assert_eq!(index.len(), manifold.simulate().timelines());
+        }
+
+        #[test]
+        fn test_len_at_edges() {
+            // Splitters in the first and last columns, each sending one
+            // beam off the side
+            let input = "S...S\n.....\n^....\n....^\n";
+            for edge in [Edge::Lose, Edge::Wrap, Edge::Clamp] {
+                let manifold = Manifold::parse(input).with_edge(edge);
+                let index = TimelineIndex::new(&manifold);
+                assert_eq!(index.len(), both_array(input, edge).1, "{edge:?}");
+                assert_eq!(index.iter().count() as u64, index.len(), "{edge:?}");
+            }
+
+            let input = "....S\n.....\n....^\n";
+            let manifold = Manifold::parse(input);
+            assert_eq!(TimelineIndex::new(&manifold).len(), 2);
+            assert_eq!(part2(input, Edge::Lose), 2);
+        }
+
+        #[test]
+        fn test_matches_simulation() {
+            let input = "\
+..S..S.
+.......
+.^^><^.
+.3.^^.#
+>.^.^.<
+.......
+";
+            for edge in [Edge::Lose, Edge::Wrap, Edge::Clamp] {
+                let manifold = Manifold::parse(input).with_edge(edge);
+                let simulation = manifold.simulate();
+                let index = TimelineIndex::new(&manifold);
+                assert_eq!(index.len(), simulation.timelines(), "{edge:?}");
+
+                let mut exits = vec![0u64; manifold.width()];
+                for timeline in index.iter() {
+                    if let End::Bottom(col) = timeline.end {
+                        exits[col] += 1;
+                    }
+                }
+                assert_eq!(exits, simulation.exits, "{edge:?}");
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{part1, part2, both, both_array};
+    use super::{manifold::{Edge, Manifold}, timeline::TimelineIndex};
     
     static EXAMPLE_INPUT: &str = include_str!("../example.txt");
     static FULL_INPUT: &str = include_str!("../input.txt");
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(part1(EXAMPLE_INPUT), 21);
+        assert_eq!(part1(EXAMPLE_INPUT, Edge::Lose), 21);
     }
 
     #[test]
     fn test_part1_full() {
-        assert_eq!(part1(FULL_INPUT), 1687);
+        assert_eq!(part1(FULL_INPUT, Edge::Lose), 1687);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE_INPUT), 40);
+        assert_eq!(part2(EXAMPLE_INPUT, Edge::Lose), 40);
     }
 
     #[test]
     fn test_part2_full() {
-        assert_eq!(part2(FULL_INPUT), 390684413472684);
+        assert_eq!(part2(FULL_INPUT, Edge::Lose), 390684413472684);
     }
 
     #[test]
     fn test_both_example() {
-        assert_eq!(both(EXAMPLE_INPUT), (21, 40));
+        assert_eq!(both(EXAMPLE_INPUT, Edge::Lose), (21, 40));
     }
 
     #[test]
     fn test_both_full() {
-        assert_eq!(both(FULL_INPUT), (1687, 390684413472684));
+        assert_eq!(both(FULL_INPUT, Edge::Lose), (1687, 390684413472684));
     }
 
     #[test]
     fn test_both_array_example() {
-        assert_eq!(both_array(EXAMPLE_INPUT), (21, 40));
+        assert_eq!(both_array(EXAMPLE_INPUT, Edge::Lose), (21, 40));
     }
 
     #[test]
     fn test_both_array_full() {
-        assert_eq!(both_array(FULL_INPUT), (1687, 390684413472684));
+        assert_eq!(both_array(FULL_INPUT, Edge::Lose), (1687, 390684413472684));
+    }
+
+    #[test]
+    fn test_splitters_at_edges() {
+        // The beam split off the edge leaves the manifold, but it is still
+        // a timeline
+        let input = "S.....\n......\n^....\n.....^\n";
+        assert_eq!(part1(input, Edge::Lose), 1);
+        assert_eq!(part2(input, Edge::Lose), 2);
+        assert_eq!(both(input, Edge::Lose), (1, 2));
+        assert_eq!(both_array(input, Edge::Lose), (1, 2));
+
+        let input = ".....S\n......\n.....^\n";
+        assert_eq!(both(input, Edge::Lose), (1, 2));
+        assert_eq!(both_array(input, Edge::Lose), (1, 2));
+    }
+
+    #[test]
+    fn test_edge_timelines_match_index() {
+        for input in ["S..\n...\n^..\n", "..S\n...\n..^\n", ".S.\n...\n.^.\n^..\n"] {
+            let manifold = Manifold::parse(input);
+            assert_eq!(part2(input, Edge::Lose), TimelineIndex::new(&manifold).len(), "{input:?}");
+            assert_eq!(part2(input, Edge::Lose), manifold.simulate().timelines(), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn test_edges_match_simulation() {
+        let inputs = [EXAMPLE_INPUT, "S..\n...\n^..\n", ".S.\n...\n.^.\n^..\n", "..S\n...\n.^^\n...\n"];
+        for edge in [Edge::Lose, Edge::Wrap, Edge::Clamp] {
+            for input in inputs {
+                let simulation = Manifold::parse(input).with_edge(edge).simulate();
+                let expected = (simulation.splits(), simulation.timelines());
+                assert_eq!(part1(input, edge), expected.0, "{edge:?} {input:?}");
+                assert_eq!(part2(input, edge), expected.1, "{edge:?} {input:?}");
+                assert_eq!(both(input, edge), expected, "{edge:?} {input:?}");
+                assert_eq!(both_array(input, edge), expected, "{edge:?} {input:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_adjacent_splitters() {
+        // The beam split right by the first splitter is split again by
+        // the second one, in the same row
+        let input = "..S..\n.....\n..^^.\n.....\n";
+        assert_eq!(part1(input, Edge::Lose), 2);
+        assert_eq!(part2(input, Edge::Lose), 3);
+        assert_eq!(both(input, Edge::Lose), (2, 3));
+        assert_eq!(both_array(input, Edge::Lose), (2, 3));
+
+        let manifold = Manifold::parse(input);
+        let simulation = manifold.simulate();
+        assert_eq!((simulation.splits(), simulation.timelines()), (2, 3));
+        assert_eq!(simulation.exits, [0, 1, 1, 0, 1]);
+        assert_eq!(TimelineIndex::new(&manifold).len(), 3);
+
+        // Split left onto a splitter, the beam isn't split again until the
+        // next row
+        let input = "...S.\n.....\n..^^.\n.....\n";
+        assert_eq!(both(input, Edge::Lose), (1, 2));
+        assert_eq!(both_array(input, Edge::Lose), (1, 2));
+        let manifold = Manifold::parse(input);
+        assert_eq!(manifold.simulate().timelines(), 2);
+        assert_eq!(TimelineIndex::new(&manifold).len(), 2);
     }}
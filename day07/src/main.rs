@@ -1,9 +1,16 @@
-use day07::both_array;
+use day07::{both_array, manifold::{Edge, Manifold}};
 
 fn main() {
     let input = include_str!("../input.txt");
 
-    let (result1, result2) = both_array(input);
+    let (result1, result2) = both_array(input, Edge::default());
     println!("Part 1: {result1}");
     println!("Part 2: {result2}");
+
+    // With `--render`, draw the manifold with all of its beams.
+    if std::env::args().any(|arg| arg == "--render") {
+        let manifold = Manifold::parse(input);
+        println!();
+        print!("{}", manifold.simulate().render(&manifold));
+    }
 }
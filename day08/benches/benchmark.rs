@@ -1,4 +1,5 @@
-use day08::{ part1, part2, both };
+use day08::{ part1, part2, both, part1_streaming, part2_streaming, both_streaming };
+use day08::{ generated_input, generated_clusters };
 
 fn main() {
     divan::main();
@@ -20,3 +21,38 @@ fn bench_part2() {
 fn bench_both() {
     both(INPUT, 1000);
 }
+
+#[divan::bench]
+fn bench_part1_streaming() {
+    part1_streaming(INPUT, 1000);
+}
+
+#[divan::bench]
+fn bench_part2_streaming() {
+    part2_streaming(INPUT);
+}
+
+#[divan::bench]
+fn bench_both_streaming() {
+    both_streaming(INPUT, 1000);
+}
+
+const SIZES: [usize; 3] = [10_000, 50_000, 100_000];
+
+#[divan::bench(args = SIZES, sample_count = 10)]
+fn bench_large_both_streaming(bencher: divan::Bencher, size: usize) {
+    let input = generated_input(size);
+    bencher.bench(|| both_streaming(&input, 1000));
+}
+
+#[divan::bench(args = SIZES, sample_count = 10)]
+fn bench_large_part2_streaming(bencher: divan::Bencher, size: usize) {
+    let input = generated_input(size);
+    bencher.bench(|| part2_streaming(&input));
+}
+
+#[divan::bench(args = SIZES, sample_count = 10)]
+fn bench_clustered_part2_streaming(bencher: divan::Bencher, size: usize) {
+    let input = generated_clusters(size);
+    bencher.bench(|| part2_streaming(&input));
+}
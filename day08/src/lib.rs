@@ -1,14 +1,18 @@
 use std::collections::BinaryHeap;
 use core::cmp::Reverse;
-use rustc_hash::FxHashSet as HashSet;
 use itertools::Itertools;
-use parsing::parse_input;
+pub use parsing::parse_input;
+use kd_tree::{ClosestPairs, minimum_spanning_tree};
+pub use union_find::UnionFind;
+pub use spanning_tree::{Edge, SpanningTree};
+pub use circuits::{Circuits, Connection};
 
-#[derive(Hash, PartialEq, Eq, PartialOrd, Ord)]
-struct Point {
-    x: u64,
-    y: u64,
-    z: u64
+// A junction box
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point {
+    pub x: u64,
+    pub y: u64,
+    pub z: u64
 }
 
 impl std::fmt::Debug for Point {
@@ -18,115 +22,89 @@ impl std::fmt::Debug for Point {
 }
 
 impl Point {
+    // The coordinate along the given axis (0 = x, 1 = y, 2 = z)
+    fn coordinate(&self, axis: usize) -> u64 {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
     // Return the square of the Euclidean distance from self to other
-    fn distance_to(&self, other: &Self) -> u64 {
+    pub fn distance_to(&self, other: &Self) -> u64 {
         self.x.abs_diff(other.x).pow(2) +
         self.y.abs_diff(other.y).pow(2) +
         self.z.abs_diff(other.z).pow(2)
     }
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-struct Pair<'a> {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Pair {
     // Note: distance must be first so that pairs are sorted by distance
     distance: u64,
-    p1: &'a Point,
-    p2: &'a Point,
+    // Indices of the two points
+    p1: usize,
+    p2: usize,
 }
 
-impl<'a> Pair<'a> {
-    fn new(p1: &'a Point, p2: &'a Point) -> Self {
-        Pair { p1, p2, distance: p1.distance_to(p2) }
+impl Pair {
+    fn new(points: &[Point], p1: usize, p2: usize) -> Self {
+        Pair { p1, p2, distance: points[p1].distance_to(&points[p2]) }
     }
 }
 
+// Produce a list of all unique pairs of points
+fn all_pairs(points: &[Point]) -> impl Iterator<Item = Pair> {
+    (0..points.len())
+        .tuple_combinations()
+        .map(|(p1, p2)| Pair::new(points, p1, p2))
+}
+
 pub fn part1(input: &str, num_connections: usize) -> usize {
     // Parse the input
     let points = parse_input(input);
 
-    // Produce a list of all unique pairs of points, sorted by
-    // distance between the points.  Sorted from largest distance
-    // to smallest distance, so that the smallest can be .pop()'ed.
-    let mut pairs = points.iter()
-        .combinations(2)
-        .map(|p| Pair::new(p[0], p[1]))
-        .collect_vec() ;
+    // Sort the pairs from largest distance to smallest distance,
+    // so that the smallest can be .pop()'ed.
+    let mut pairs = all_pairs(&points).collect_vec();
     pairs.sort_unstable_by_key(|pair| pair.distance);
     pairs.reverse();
 
-    // Create a list/set of components (circuits).
-    // Initially, each point is in its own separate component.
-    let mut circuits = points.iter().map(|point| {
-        let mut set = HashSet::default();
-        set.insert(point);
-        set
-    }).collect_vec();
+    // Initially, each point is in its own separate component (circuit).
+    let mut circuits = UnionFind::new(points.len());
 
     for _ in 0..num_connections {
-        // Find and remove the shortest remaining distance -> two points
+        // Find and remove the shortest remaining distance -> two points,
+        // and connect them if they are in different circuits.
         let pair = pairs.pop().unwrap();
-
-        // If the two points are in different circuits, then connect them.
-        // The hard part here is getting mutable refences to both circuits
-        // at the same time.
-        let c1 = circuits.iter().position(|circuit| circuit.contains(pair.p1)).unwrap();
-        if !circuits[c1].contains(pair.p2) {
-            let c2 = circuits.iter().position(|circuit| circuit.contains(pair.p2)).unwrap();
-            let c_min = c1.min(c2);
-            let c_max = c1.max(c2);
-            let (part1, part2) = circuits.split_at_mut(c_max);
-            part1[c_min].extend(part2[0].drain());
-            circuits.remove(c_max);
-        }
+        circuits.union(pair.p1, pair.p2);
     }
 
-    // Sort components/circuits by number of points
-    // Return the sum of the three largest sizes
-    let mut lengths = circuits.iter().map(|circuit| circuit.len()).collect_vec();
-    lengths.sort_unstable();
-    lengths.iter().rev().take(3).product()
+    // Return the product of the three largest circuit sizes
+    circuits.component_sizes().into_iter().k_largest(3).product()
 }
 
 pub fn part2(input: &str) -> u64 {
     // Parse the input
     let points = parse_input(input);
 
-    // Produce a list of all unique pairs of points, sorted by
-    // distance between the points.  Sorted from largest distance
-    // to smallest distance, so that the smallest can be .pop()'ed.
-    let mut pairs = points.iter()
-        .combinations(2)
-        .map(|p| Pair::new(p[0], p[1]))
-        .collect_vec() ;
+    // Sort the pairs from largest distance to smallest distance,
+    // so that the smallest can be .pop()'ed.
+    let mut pairs = all_pairs(&points).collect_vec();
     pairs.sort_unstable_by_key(|pair| pair.distance);
     pairs.reverse();
 
-    // Create a list/set of components (circuits).
-    // Initially, each point is in its own separate component.
-    let mut circuits = points.iter().map(|point| {
-        let mut set = HashSet::default();
-        set.insert(point);
-        set
-    }).collect_vec();
+    // Initially, each point is in its own separate component (circuit).
+    let mut circuits = UnionFind::new(points.len());
 
     loop {
         // Find and remove the shortest remaining distance -> two points
         let pair = pairs.pop().unwrap();
 
         // If the two points are in different circuits, then connect them.
-        // The hard part here is getting mutable refences to both circuits
-        // at the same time.
-        let c1 = circuits.iter().position(|circuit| circuit.contains(pair.p1)).unwrap();
-        if !circuits[c1].contains(pair.p2) {
-            if circuits.len() == 2 {
-                return pair.p1.x * pair.p2.x;
-            }
-            let c2 = circuits.iter().position(|circuit| circuit.contains(pair.p2)).unwrap();
-            let c_min = c1.min(c2);
-            let c_max = c1.max(c2);
-            let (part1, part2) = circuits.split_at_mut(c_max);
-            part1[c_min].extend(part2[0].drain());
-            circuits.remove(c_max);
+        if circuits.union(pair.p1, pair.p2) && circuits.components() == 1 {
+            return points[pair.p1].x * points[pair.p2].x;
         }
     }
 }
@@ -137,53 +115,826 @@ pub fn both(input: &str, num_connections: usize) -> (usize, u64) {
     // Parse the input
     let points = parse_input(input);
 
-    // Produce a list of all unique pairs of points, sorted by
-    // distance between the points.
-    let pairs = points.iter()
-        .combinations(2)
-        .map(|p| Reverse(Pair::new(p[0], p[1])))
-        .collect_vec() ;
-    let mut pairs = BinaryHeap::from(pairs);
-
-    // Create a list/set of components (circuits).
-    // Initially, each point is in its own separate component.
-    let mut circuits = points.iter().map(|point| {
-        let mut set = HashSet::default();
-        set.insert(point);
-        set
-    }).collect_vec();
+    // All unique pairs of points, popped in order of distance.
+    let mut pairs = BinaryHeap::from_iter(all_pairs(&points).map(Reverse));
+
+    // Initially, each point is in its own separate component (circuit).
+    let mut circuits = UnionFind::new(points.len());
 
     let mut num_iterations = 0;
     while let Some(Reverse(pair)) = pairs.pop() {
         if num_iterations == num_connections {
-            result1 = circuits.iter()
-                .map(|circuit| circuit.len())
+            result1 = circuits.component_sizes()
+                .into_iter()
                 .k_largest(3)
                 .product();
         }
         num_iterations += 1;
 
         // If the two points are in different circuits, then connect them.
-        // The hard part here is getting mutable refences to both circuits
-        // at the same time.
-        let c1 = circuits.iter().position(|circuit| circuit.contains(pair.p1)).unwrap();
-        if !circuits[c1].contains(pair.p2) {
-            if circuits.len() == 2 {
-                // Connecting into a single circuit
-                return (result1, pair.p1.x * pair.p2.x);
-            }
-            let c2 = circuits.iter().position(|circuit| circuit.contains(pair.p2)).unwrap();
-            let c_min = c1.min(c2);
-            let c_max = c1.max(c2);
-            let (part1, part2) = circuits.split_at_mut(c_max);
-            part1[c_min].extend(part2[0].drain());
-            circuits.remove(c_max);
+        if circuits.union(pair.p1, pair.p2) && circuits.components() == 1 {
+            // Connected into a single circuit
+            return (result1, points[pair.p1].x * points[pair.p2].x);
         }
     }
 
     unreachable!()
 }
 
+//
+// The same, but using a k-d tree instead of building and sorting every
+// pair of points.  Part 1 takes pairs in order from the tree, so its work
+// grows with the number of connections, not the square of the number of
+// points.  Part 2 finds the minimum spanning tree directly; its longest
+// edge is the connection that joins the last two circuits.
+//
+pub fn part1_streaming(input: &str, num_connections: usize) -> usize {
+    let points = parse_input(input);
+    let mut circuits = Circuits::new(&points);
+    circuits.advance(num_connections);
+    circuits.top_sizes(3).into_iter().product()
+}
+
+pub fn part2_streaming(input: &str) -> u64 {
+    let points = parse_input(input);
+    let last = minimum_spanning_tree(&points)
+        .pop()
+        .expect("Points never connect into one circuit");
+    points[last.p1].x * points[last.p2].x
+}
+
+pub fn both_streaming(input: &str, num_connections: usize) -> (usize, u64) {
+    let points = parse_input(input);
+    let mut circuits = UnionFind::new(points.len());
+
+    for pair in ClosestPairs::new(&points).take(num_connections) {
+        if circuits.union(pair.p1, pair.p2) && circuits.components() == 1 {
+            // Connected into a single circuit before part 1's connections
+            // were all made, as in `both`
+            return (0, points[pair.p1].x * points[pair.p2].x);
+        }
+    }
+    let result1 = circuits.component_sizes()
+        .into_iter()
+        .k_largest(3)
+        .product();
+
+    let last = minimum_spanning_tree(&points)
+        .pop()
+        .expect("Points never connect into one circuit");
+    (result1, points[last.p1].x * points[last.p2].x)
+}
+
+//
+// A k-d tree over the points, used to produce pairs of points in order
+// of increasing distance, and to find the minimum spanning tree, without
+// materializing all of the pairs.
+//
+mod kd_tree {
+    use std::collections::BinaryHeap;
+    use core::cmp::Reverse;
+    use super::{Point, Pair, UnionFind};
+
+    pub(crate) struct KdTree<'a> {
+        points: &'a [Point],
+        // Point indices; each subslice's middle element splits the rest
+        // along the axis for its depth
+        nodes: Vec<usize>,
+        // The bounding box (lowest and highest corners) of each subtree,
+        // by the position of its root in `nodes`
+        bounds: Vec<([u64; 3], [u64; 3])>,
+    }
+
+    impl<'a> KdTree<'a> {
+        pub fn new(points: &'a [Point]) -> Self {
+            let mut nodes: Vec<usize> = (0..points.len()).collect();
+            Self::build(points, &mut nodes, 0);
+            let mut tree = KdTree { points, nodes, bounds: vec![([0; 3], [0; 3]); points.len()] };
+            tree.bound(0, points.len());
+            tree
+        }
+
+        fn bound(&mut self, lo: usize, hi: usize) -> Option<([u64; 3], [u64; 3])> {
+            if lo == hi {
+                return None;
+            }
+            let mid = (lo + hi) / 2;
+            let point = &self.points[self.nodes[mid]];
+            let (mut low, mut high) = ([point.x, point.y, point.z], [point.x, point.y, point.z]);
+            for (child_low, child_high) in [self.bound(lo, mid), self.bound(mid + 1, hi)].into_iter().flatten() {
+                for axis in 0..3 {
+                    low[axis] = low[axis].min(child_low[axis]);
+                    high[axis] = high[axis].max(child_high[axis]);
+                }
+            }
+            self.bounds[mid] = (low, high);
+            Some((low, high))
+        }
+
+        // The square of the distance from `target` to the nearest point
+        // of the bounding box of the subtree rooted at `nodes[mid]`
+        fn distance_to_bounds(&self, target: usize, mid: usize) -> u64 {
+            let (low, high) = &self.bounds[mid];
+            (0..3).map(|axis| {
+                let here = self.points[target].coordinate(axis);
+                let gap = low[axis].saturating_sub(here).max(here.saturating_sub(high[axis]));
+                gap.pow(2)
+            }).sum()
+        }
+
+        fn build(points: &[Point], nodes: &mut [usize], depth: usize) {
+            if nodes.len() <= 1 {
+                return;
+            }
+            let mid = nodes.len() / 2;
+            nodes.select_nth_unstable_by_key(mid, |&i| points[i].coordinate(depth % 3));
+            let (left, right) = nodes.split_at_mut(mid);
+            Self::build(points, left, depth + 1);
+            Self::build(points, &mut right[1..], depth + 1);
+        }
+
+        // The `k` points closest to `target` (excluding itself), as
+        // (distance, index), ordered by distance then index.
+        pub fn nearest(&self, target: usize, k: usize) -> Vec<(u64, usize)> {
+            let mut best = BinaryHeap::with_capacity(k + 1);
+            if k > 0 {
+                self.search(&self.nodes, 0, target, k, &mut best);
+            }
+            best.into_sorted_vec()
+        }
+
+        fn search(&self, nodes: &[usize], depth: usize, target: usize, k: usize, best: &mut BinaryHeap<(u64, usize)>) {
+            if nodes.is_empty() {
+                return;
+            }
+            let mid = nodes.len() / 2;
+            let node = nodes[mid];
+            if node != target {
+                let candidate = (self.points[target].distance_to(&self.points[node]), node);
+                if best.len() < k {
+                    best.push(candidate);
+                } else if candidate < *best.peek().unwrap() {
+                    best.pop();
+                    best.push(candidate);
+                }
+            }
+
+            let axis = depth % 3;
+            let (here, split) = (self.points[target].coordinate(axis), self.points[node].coordinate(axis));
+            let (near, far) = if here < split {
+                (&nodes[..mid], &nodes[mid + 1..])
+            } else {
+                (&nodes[mid + 1..], &nodes[..mid])
+            };
+            self.search(near, depth + 1, target, k, best);
+
+            // Points on the far side are at least this far away.  Equal
+            // distances still matter, since ties are broken by index.
+            if best.len() < k || here.abs_diff(split).pow(2) <= best.peek().unwrap().0 {
+                self.search(far, depth + 1, target, k, best);
+            }
+        }
+
+        //
+        // Record in `labels`, for each subtree of `nodes[lo..hi]` (by the
+        // position of its root), the circuit all of its points are in, or
+        // `None` if they are in more than one.  Returns the label for the
+        // whole range.
+        //
+        fn label(&self, lo: usize, hi: usize, circuit: &[usize], labels: &mut [Option<usize>]) -> Option<usize> {
+            if lo == hi {
+                return None;
+            }
+            let mid = (lo + hi) / 2;
+            let own = circuit[self.nodes[mid]];
+            let left = self.label(lo, mid, circuit, labels);
+            let right = self.label(mid + 1, hi, circuit, labels);
+            let same = (lo == mid || left == Some(own)) && (mid + 1 == hi || right == Some(own));
+            labels[mid] = same.then_some(own);
+            labels[mid]
+        }
+
+        //
+        // Update `best` if there is a shorter pair from `target` to a point
+        // in `nodes[lo..hi]` in a different circuit.  Subtrees entirely in
+        // `target`'s circuit, or further away than `best`, are skipped.
+        //
+        fn nearest_outside(&self, (lo, hi): (usize, usize), depth: usize, target: usize, labels: &Labels, best: &mut Option<Pair>) {
+            if lo == hi {
+                return;
+            }
+            let mid = (lo + hi) / 2;
+            let own = labels.circuit[target];
+            if labels.subtrees[mid] == Some(own) {
+                return;
+            }
+            // Equal distances still matter, since ties are broken by index
+            if best.is_some_and(|best| self.distance_to_bounds(target, mid) > best.distance) {
+                return;
+            }
+
+            let node = self.nodes[mid];
+            if labels.circuit[node] != own {
+                let distance = self.points[target].distance_to(&self.points[node]);
+                let candidate = Pair { distance, p1: target.min(node), p2: target.max(node) };
+                if best.is_none_or(|best| candidate < best) {
+                    *best = Some(candidate);
+                }
+            }
+
+            let axis = depth % 3;
+            let (here, split) = (self.points[target].coordinate(axis), self.points[node].coordinate(axis));
+            let (near, far) = if here < split {
+                ((lo, mid), (mid + 1, hi))
+            } else {
+                ((mid + 1, hi), (lo, mid))
+            };
+            self.nearest_outside(near, depth + 1, target, labels, best);
+            self.nearest_outside(far, depth + 1, target, labels, best);
+        }
+    }
+
+    // Each point's circuit, and each subtree's from `KdTree::label`
+    struct Labels {
+        circuit: Vec<usize>,
+        subtrees: Vec<Option<usize>>,
+    }
+
+    //
+    // The Euclidean minimum spanning tree, by Borůvka's algorithm.  In
+    // each round, every circuit finds its shortest pair to a point in
+    // another circuit, and all of those pairs are added, so the number of
+    // circuits at least halves.  Pairs are compared by distance and then
+    // by index, as in `ClosestPairs`, so this is the same tree Kruskal's
+    // algorithm would build from them.  Returns the pairs in that order.
+    //
+    pub(crate) fn minimum_spanning_tree(points: &[Point]) -> Vec<Pair> {
+        let tree = KdTree::new(points);
+        let mut circuits = UnionFind::new(points.len());
+        let mut edges = Vec::with_capacity(points.len().saturating_sub(1));
+        let mut labels = Labels { circuit: Vec::new(), subtrees: vec![None; points.len()] };
+
+        while circuits.components() > 1 {
+            labels.circuit = (0..points.len()).map(|point| circuits.find(point)).collect();
+            tree.label(0, points.len(), &labels.circuit, &mut labels.subtrees);
+
+            // The shortest pair out of each circuit, by representative
+            let mut shortest: Vec<Option<Pair>> = vec![None; points.len()];
+            for point in 0..points.len() {
+                let best = &mut shortest[labels.circuit[point]];
+                tree.nearest_outside((0, points.len()), 0, point, &labels, best);
+            }
+
+            // Two circuits may have picked the same pair
+            for pair in shortest.into_iter().flatten() {
+                if circuits.union(pair.p1, pair.p2) {
+                    edges.push(pair);
+                }
+            }
+        }
+
+        edges.sort_unstable();
+        edges
+    }
+
+    // The neighbors of one point found so far, and how many were used
+    struct Neighbors {
+        found: Vec<(u64, usize)>,
+        next: usize,
+    }
+
+    //
+    // Every pair of points, in order of distance (then by index), each
+    // pair once.  Each point's neighbors are fetched a batch at a time,
+    // doubling the batch when it runs out, and the nearest unused
+    // neighbor of every point is kept in a heap.  A point's batch is at
+    // most twice the number of its pairs already taken, so the memory and
+    // time used grow with the number of pairs taken.  Taking every pair is
+    // still quadratic; that is what `minimum_spanning_tree` avoids.
+    //
+    pub(crate) struct ClosestPairs<'a> {
+        tree: KdTree<'a>,
+        neighbors: Vec<Neighbors>,
+        heap: BinaryHeap<Reverse<(u64, usize, usize)>>,
+    }
+
+    const FIRST_BATCH: usize = 4;
+
+    impl<'a> ClosestPairs<'a> {
+        pub fn new(points: &'a [Point]) -> Self {
+            let tree = KdTree::new(points);
+            let mut neighbors = Vec::with_capacity(points.len());
+            let mut heap = BinaryHeap::with_capacity(points.len());
+            for point in 0..points.len() {
+                let found = tree.nearest(point, FIRST_BATCH);
+                if let Some(&(distance, other)) = found.first() {
+                    heap.push(Reverse((distance, point, other)));
+                }
+                neighbors.push(Neighbors { found, next: 1 });
+            }
+            ClosestPairs { tree, neighbors, heap }
+        }
+
+        // Queue up the next neighbor of `point`, if it has one
+        fn advance(&mut self, point: usize) {
+            let neighbors = &mut self.neighbors[point];
+            if neighbors.next == neighbors.found.len() {
+                if neighbors.found.len() + 1 >= self.tree.points.len() {
+                    return;
+                }
+                neighbors.found = self.tree.nearest(point, neighbors.found.len() * 2);
+            }
+            let (distance, other) = neighbors.found[neighbors.next];
+            neighbors.next += 1;
+            self.heap.push(Reverse((distance, point, other)));
+        }
+    }
+
+    impl Iterator for ClosestPairs<'_> {
+        type Item = Pair;
+
+        fn next(&mut self) -> Option<Pair> {
+            // Each pair comes up once from each end; keep the one from the
+            // lower index
+            loop {
+                let Reverse((distance, point, other)) = self.heap.pop()?;
+                self.advance(point);
+                if point < other {
+                    return Some(Pair { distance, p1: point, p2: other });
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{ClosestPairs, KdTree, minimum_spanning_tree};
+        use crate::{all_pairs, parsing::parse_input, Point, UnionFind};
+        use itertools::Itertools;
+
+        static EXAMPLE_INPUT: &str = include_str!("../example.txt");
+
+        #[test]
+        fn test_nearest_example() {
+            let points = parse_input(EXAMPLE_INPUT);
+            let tree = KdTree::new(&points);
+            for target in 0..points.len() {
+                let expected = (0..points.len())
+                    .filter(|&other| other != target)
+                    .map(|other| (points[target].distance_to(&points[other]), other))
+                    .sorted()
+                    .take(5)
+                    .collect_vec();
+                assert_eq!(tree.nearest(target, 5), expected);
+            }
+        }
+
+        #[test]
+        fn test_closest_pairs_example() {
+            let points = parse_input(EXAMPLE_INPUT);
+            let expected = all_pairs(&points)
+                .map(|pair| (pair.distance, pair.p1, pair.p2))
+                .sorted()
+                .collect_vec();
+            let streamed = ClosestPairs::new(&points)
+                .map(|pair| (pair.distance, pair.p1, pair.p2))
+                .collect_vec();
+            assert_eq!(streamed, expected);
+        }
+
+        // Kruskal's algorithm over every pair
+        fn kruskal(points: &[Point]) -> Vec<(u64, usize, usize)> {
+            let mut circuits = UnionFind::new(points.len());
+            all_pairs(points)
+                .sorted()
+                .filter(|pair| circuits.union(pair.p1, pair.p2))
+                .map(|pair| (pair.distance, pair.p1, pair.p2))
+                .collect()
+        }
+
+        fn boruvka(points: &[Point]) -> Vec<(u64, usize, usize)> {
+            minimum_spanning_tree(points).into_iter()
+                .map(|pair| (pair.distance, pair.p1, pair.p2))
+                .collect()
+        }
+
+        #[test]
+        fn test_minimum_spanning_tree_example() {
+            let points = parse_input(EXAMPLE_INPUT);
+            assert_eq!(boruvka(&points), kruskal(&points));
+        }
+
+        #[test]
+        fn test_minimum_spanning_tree_ties() {
+            // Repeated points, and many pairs at the same distance
+            let input = (0..60).map(|i| format!("{},{},{}\n", i % 4, i % 3, i % 5 / 2)).collect::<String>();
+            let points = parse_input(&input);
+            assert_eq!(boruvka(&points), kruskal(&points));
+        }
+
+        #[test]
+        fn test_minimum_spanning_tree_tiny() {
+            assert_eq!(boruvka(&[]), []);
+            assert_eq!(boruvka(&parse_input("1,2,3\n")), []);
+        }
+    }
+}
+
+//
+// The minimum spanning tree connecting all of the junction boxes.  This
+// is the tree Kruskal's algorithm builds, taking pairs in order of
+// distance and keeping those that join two different circuits, but it is
+// found directly from the k-d tree, without going through all the pairs.
+//
+pub mod spanning_tree {
+    use super::{Point, kd_tree::minimum_spanning_tree, parsing::parse_input};
+
+    //
+    // A cable between the points with indices `p1` and `p2` (in input
+    // order), and the number of circuits once it has been added.
+    //
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Edge {
+        pub p1: usize,
+        pub p2: usize,
+        pub squared_distance: u64,
+        pub components: usize,
+    }
+
+    impl Edge {
+        pub fn length(&self) -> f64 {
+            (self.squared_distance as f64).sqrt()
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SpanningTree {
+        pub points: Vec<Point>,
+        // In the order they were added, which is by increasing distance
+        pub edges: Vec<Edge>,
+    }
+
+    impl SpanningTree {
+        pub fn new(points: Vec<Point>) -> SpanningTree {
+            // Each edge joins two circuits into one
+            let edges = minimum_spanning_tree(&points).into_iter()
+                .enumerate()
+                .map(|(step, pair)| Edge {
+                    p1: pair.p1,
+                    p2: pair.p2,
+                    squared_distance: pair.distance,
+                    components: points.len() - step - 1,
+                })
+                .collect();
+
+            SpanningTree { points, edges }
+        }
+
+        pub fn parse(input: &str) -> SpanningTree {
+            SpanningTree::new(parse_input(input))
+        }
+
+        // The total Euclidean length of all of the edges
+        pub fn total_length(&self) -> f64 {
+            self.edges.iter().map(Edge::length).sum()
+        }
+
+        // The edge that finally connects everything into one circuit
+        pub fn last_edge(&self) -> Option<&Edge> {
+            self.edges.last()
+        }
+
+        // The number of circuits before any edges, then after each edge
+        pub fn component_counts(&self) -> Vec<usize> {
+            std::iter::once(self.points.len())
+                .chain(self.edges.iter().map(|edge| edge.components))
+                .collect()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{Edge, SpanningTree};
+        use crate::UnionFind;
+        use crate::{all_pairs, parsing::parse_input};
+        use itertools::Itertools;
+
+        static EXAMPLE_INPUT: &str = include_str!("../example.txt");
+
+        #[test]
+        fn test_spanning_tree_example() {
+            let tree = SpanningTree::parse(EXAMPLE_INPUT);
+            assert_eq!(tree.edges.len(), 19);
+            assert_eq!(tree.component_counts(), (1..=20).rev().collect_vec());
+
+            let &Edge { p1, p2, .. } = tree.last_edge().unwrap();
+            assert_eq!(tree.points[p1].x * tree.points[p2].x, 25272);
+
+            assert!(tree.edges.iter().tuple_windows().all(|(a, b)| a.squared_distance <= b.squared_distance));
+            assert_eq!(tree.edges.iter().map(|edge| edge.squared_distance).sum::<u64>(), 2596246);
+            assert!((tree.total_length() - 6987.768314127).abs() < 1e-6);
+        }
+
+        #[test]
+        fn test_spanning_tree_matches_all_pairs() {
+            // Kruskal's algorithm over every pair
+            let points = parse_input(EXAMPLE_INPUT);
+            let mut circuits = UnionFind::new(points.len());
+            let expected: u64 = all_pairs(&points)
+                .sorted()
+                .filter(|pair| circuits.union(pair.p1, pair.p2))
+                .map(|pair| pair.distance)
+                .sum();
+
+            let tree = SpanningTree::new(points);
+            assert_eq!(tree.edges.iter().map(|edge| edge.squared_distance).sum::<u64>(), expected);
+        }
+
+        #[test]
+        fn test_spanning_tree_tiny() {
+            assert_eq!(SpanningTree::parse("1,2,3\n").edges, []);
+            let tree = SpanningTree::parse("0,0,0\n3,4,0\n");
+            assert_eq!(tree.edges, [Edge { p1: 0, p2: 1, squared_distance: 25, components: 1 }]);
+            assert_eq!(tree.total_length(), 5.0);
+        }
+    }
+}
+
+//
+// The state of the circuits as connections are made, shortest first.
+// It can be rolled forward any number of connections at a time, and
+// queried in between.
+//
+pub mod circuits {
+    use itertools::Itertools;
+    use rustc_hash::FxHashMap as HashMap;
+    use super::{Point, UnionFind, kd_tree::ClosestPairs};
+
+    //
+    // A connection between the points with indices `p1` and `p2`, and
+    // whether it merged two circuits (rather than being within one).
+    //
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Connection {
+        pub p1: usize,
+        pub p2: usize,
+        pub squared_distance: u64,
+        pub merged: bool,
+    }
+
+    pub struct Circuits<'a> {
+        pairs: ClosestPairs<'a>,
+        sets: UnionFind,
+        connections: usize,
+    }
+
+    impl<'a> Circuits<'a> {
+        // Initially, each point is a circuit of its own
+        pub fn new(points: &'a [Point]) -> Circuits<'a> {
+            Circuits {
+                pairs: ClosestPairs::new(points),
+                sets: UnionFind::new(points.len()),
+                connections: 0,
+            }
+        }
+
+        // Make the next shortest connection, if there are any left
+        pub fn connect_next(&mut self) -> Option<Connection> {
+            let pair = self.pairs.next()?;
+            self.connections += 1;
+            let merged = self.sets.union(pair.p1, pair.p2);
+            Some(Connection { p1: pair.p1, p2: pair.p2, squared_distance: pair.distance, merged })
+        }
+
+        // Make up to `count` more connections, returning how many were made
+        pub fn advance(&mut self, count: usize) -> usize {
+            (0..count).map_while(|_| self.connect_next()).count()
+        }
+
+        // The number of connections made so far
+        pub fn connections(&self) -> usize {
+            self.connections
+        }
+
+        // The number of circuits
+        pub fn len(&self) -> usize {
+            self.sets.components()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.sets.is_empty()
+        }
+
+        pub fn connected(&mut self, a: usize, b: usize) -> bool {
+            self.sets.connected(a, b)
+        }
+
+        // The size of the circuit containing `point`
+        pub fn size_of(&mut self, point: usize) -> usize {
+            self.sets.size_of(point)
+        }
+
+        pub fn sizes(&self) -> Vec<usize> {
+            self.sets.component_sizes()
+        }
+
+        // The `k` largest circuit sizes, largest first
+        pub fn top_sizes(&self, k: usize) -> Vec<usize> {
+            self.sets.component_sizes().into_iter().k_largest(k).collect()
+        }
+
+        // The members of every circuit, each in increasing order, with
+        // the circuits ordered by their lowest member
+        pub fn members(&mut self) -> Vec<Vec<usize>> {
+            let mut circuits = HashMap::<usize, usize>::default();
+            let mut members: Vec<Vec<usize>> = Vec::new();
+            for point in 0..self.sets.len() {
+                let root = self.sets.find(point);
+                let circuit = *circuits.entry(root).or_insert_with(|| {
+                    members.push(Vec::new());
+                    members.len() - 1
+                });
+                members[circuit].push(point);
+            }
+            members
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::Circuits;
+        use crate::parse_input;
+
+        static EXAMPLE_INPUT: &str = include_str!("../example.txt");
+
+        #[test]
+        fn test_circuits_example() {
+            let points = parse_input(EXAMPLE_INPUT);
+            let mut circuits = Circuits::new(&points);
+            assert_eq!(circuits.len(), 20);
+            assert_eq!(circuits.top_sizes(2), [1, 1]);
+
+            assert_eq!(circuits.advance(10), 10);
+            assert_eq!(circuits.connections(), 10);
+            assert_eq!(circuits.len(), 11);
+            assert_eq!(circuits.top_sizes(3), [5, 4, 2]);
+            assert_eq!(circuits.top_sizes(5), [5, 4, 2, 2, 1]);
+            assert_eq!(circuits.sizes().iter().sum::<usize>(), 20);
+
+            let members = circuits.members();
+            assert_eq!(members.len(), 11);
+            let largest = members.iter().find(|circuit| circuit.len() == 5).unwrap();
+            assert!(circuits.connected(largest[0], largest[4]));
+            assert_eq!(circuits.size_of(largest[2]), 5);
+        }
+
+        #[test]
+        fn test_circuits_incremental() {
+            let points = parse_input(EXAMPLE_INPUT);
+            let mut stepped = Circuits::new(&points);
+            stepped.advance(4);
+            stepped.advance(6);
+
+            let mut direct = Circuits::new(&points);
+            direct.advance(10);
+            assert_eq!(stepped.members(), direct.members());
+
+            // Roll forward until everything is one circuit
+            let last = loop {
+                let connection = stepped.connect_next().unwrap();
+                if connection.merged && stepped.len() == 1 {
+                    break connection;
+                }
+            };
+            assert_eq!(points[last.p1].x * points[last.p2].x, 25272);
+        }
+
+        #[test]
+        fn test_circuits_run_out() {
+            let points = parse_input("0,0,0\n1,1,1\n5,5,5\n");
+            let mut circuits = Circuits::new(&points);
+            assert_eq!(circuits.advance(10), 3);
+            assert_eq!(circuits.members(), [vec![0, 1, 2]]);
+            assert_eq!(circuits.connect_next(), None);
+        }
+    }
+}
+
+//
+// A disjoint-set forest over the elements 0..n, with path compression
+// and union by size.
+//
+pub mod union_find {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct UnionFind {
+        parent: Vec<usize>,
+        size: Vec<usize>,
+        components: usize,
+    }
+
+    impl UnionFind {
+        // Each element starts in a set of its own
+        pub fn new(len: usize) -> UnionFind {
+            UnionFind {
+                parent: (0..len).collect(),
+                size: vec![1; len],
+                components: len,
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.parent.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.parent.is_empty()
+        }
+
+        // The number of disjoint sets
+        pub fn components(&self) -> usize {
+            self.components
+        }
+
+        // The representative of the set containing `element`.  Every
+        // element on the way is pointed straight at it.
+        pub fn find(&mut self, element: usize) -> usize {
+            let mut root = element;
+            while self.parent[root] != root {
+                root = self.parent[root];
+            }
+
+            let mut current = element;
+            while current != root {
+                current = std::mem::replace(&mut self.parent[current], root);
+            }
+
+            root
+        }
+
+        // Merge the sets containing `a` and `b`, attaching the smaller
+        // under the larger.  Returns false if they were already the same set.
+        pub fn union(&mut self, a: usize, b: usize) -> bool {
+            let (a, b) = (self.find(a), self.find(b));
+            if a == b {
+                return false;
+            }
+
+            let (large, small) = if self.size[a] >= self.size[b] { (a, b) } else { (b, a) };
+            self.parent[small] = large;
+            self.size[large] += self.size[small];
+            self.components -= 1;
+            true
+        }
+
+        pub fn connected(&mut self, a: usize, b: usize) -> bool {
+            self.find(a) == self.find(b)
+        }
+
+        // The number of elements in the set containing `element`
+        pub fn size_of(&mut self, element: usize) -> usize {
+            let root = self.find(element);
+            self.size[root]
+        }
+
+        // The size of every set, in order of their representatives
+        pub fn component_sizes(&self) -> Vec<usize> {
+            (0..self.len())
+                .filter(|&element| self.parent[element] == element)
+                .map(|root| self.size[root])
+                .collect()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::UnionFind;
+        use itertools::Itertools;
+
+        #[test]
+        fn test_union_find() {
+            let mut sets = UnionFind::new(6);
+            assert_eq!(sets.components(), 6);
+            assert!(sets.union(0, 1));
+            assert!(sets.union(2, 3));
+            assert!(sets.union(1, 3));
+            assert!(!sets.union(0, 2));
+            assert_eq!(sets.components(), 3);
+            assert!(sets.connected(0, 3));
+            assert!(!sets.connected(0, 4));
+            assert_eq!(sets.size_of(2), 4);
+            assert_eq!(sets.component_sizes().into_iter().sorted().collect::<Vec<_>>(), [1, 1, 4]);
+        }
+
+        #[test]
+        fn test_path_compression() {
+            let mut sets = UnionFind::new(4);
+            sets.parent = vec![0, 0, 1, 2];
+            assert_eq!(sets.find(3), 0);
+            assert_eq!(sets.parent, [0, 0, 0, 0]);
+        }
+    }
+}
+
 mod parsing {
     use super::Point;
     use nom::{IResult, Parser, character::complete::{char, newline, u64}, combinator::all_consuming, multi::many1, sequence::terminated};
@@ -206,9 +957,39 @@ mod parsing {
     }
 }
 
+//
+// Pseudo-random junction boxes, spread through a cube about the size of
+// the real input's, so that distances are unlikely to tie.  The points
+// come from an LCG, so the same count always gives the same input.  Used
+// by the tests and benchmarks.
+//
+pub fn generated_input(count: usize) -> String {
+    let mut state: u64 = 12345;
+    let mut coordinate = || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) % 100000
+    };
+    (0..count).map(|_| format!("{},{},{}\n", coordinate(), coordinate(), coordinate())).collect()
+}
+
+//
+// The same, but in two clusters far apart, so that the last connection
+// is much longer than all of the others.  Taking pairs in order until
+// everything is connected would need every pair within each cluster.
+//
+pub fn generated_clusters(count: usize) -> String {
+    generated_input(count).lines().enumerate().map(|(index, line)| {
+        let offset = if index % 2 == 0 { 0 } else { 10_000_000 };
+        line.split(',')
+            .map(|coordinate| (coordinate.parse::<u64>().unwrap() + offset).to_string())
+            .join(",") + "\n"
+    }).collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2, both};
+    use super::{part1, part2, both, part1_streaming, part2_streaming, both_streaming};
+    use super::{SpanningTree, generated_input, generated_clusters};
     
     static EXAMPLE_INPUT: &str = include_str!("../example.txt");
     static FULL_INPUT: &str = include_str!("../input.txt");
@@ -242,4 +1023,37 @@ mod tests {
     fn test_both_full() {
         assert_eq!(both(FULL_INPUT, 1000), (127551, 2347225200));
     }
+
+    #[test]
+    fn test_streaming_example() {
+        assert_eq!(part1_streaming(EXAMPLE_INPUT, 10), 40);
+        assert_eq!(part2_streaming(EXAMPLE_INPUT), 25272);
+        assert_eq!(both_streaming(EXAMPLE_INPUT, 10), (40, 25272));
+    }
+
+    #[test]
+    fn test_streaming_full() {
+        assert_eq!(both_streaming(FULL_INPUT, 1000), (127551, 2347225200));
+    }
+
+    #[test]
+    fn test_streaming_matches_all_pairs() {
+        let input = generated_input(300);
+        assert_eq!(part1_streaming(&input, 200), part1(&input, 200));
+        assert_eq!(part2_streaming(&input), part2(&input));
+        assert_eq!(both_streaming(&input, 200), both(&input, 200));
+    }
+
+    #[test]
+    fn test_streaming_clusters() {
+        let input = generated_clusters(300);
+        assert_eq!(part2_streaming(&input), part2(&input));
+        assert_eq!(both_streaming(&input, 200), both(&input, 200));
+        assert_eq!(both_streaming(&input, 30000), both(&input, 30000));
+        assert_eq!(both(&input, 30000).0, 0);
+
+        let tree = SpanningTree::parse(&input);
+        assert_eq!(tree.edges.len(), 299);
+        assert!(tree.last_edge().unwrap().squared_distance > 3 * 9_000_000u64.pow(2));
+    }
 }